@@ -8,43 +8,95 @@ use primitive_types::H256;
 pub struct UTXO {
     pub_key: H256,
     id: u64,
+    amount: u64,
 }
 
 #[wasm_bindgen]
-pub fn create_utxo(pub_key: &[u8], id: u64) -> UTXO {
+pub fn create_utxo(pub_key: &[u8], id: u64, amount: u64) -> UTXO {
     let result = UTXO {
         pub_key: H256::from_slice(pub_key),
         id,
+        amount,
     };
     return result;
 }
 
 #[wasm_bindgen]
-pub fn get_utxo_elem(pub_key: &[u8], id: u64) -> Vec<u8> {
-    return create_utxo(pub_key, id).encode();
+pub fn get_utxo_elem(pub_key: &[u8], id: u64, amount: u64) -> Vec<u8> {
+    return create_utxo(pub_key, id, amount).encode();
+}
+
+/// Returns the message that the owners of the inputs of a transaction sign(see Transaction::signing_payload
+/// in the runtime). "inputs" and "outputs" are concatenations of encoded UTXOs(see get_utxo_elem).
+#[wasm_bindgen]
+pub fn get_signing_payload(inputs: &[u8], outputs: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let mut payload = decode_utxos(inputs)?.encode();
+    decode_utxos(outputs)?.encode_to(&mut payload);
+    return Ok(payload);
+}
+
+/// Splits a concatenation of encoded UTXOs.
+fn decode_utxos(mut bytes: &[u8]) -> Result<Vec<UTXO>, JsValue> {
+    let mut utxos = Vec::new();
+    while !bytes.is_empty() {
+        utxos.push(UTXO::decode(&mut bytes).map_err(|_| JsValue::from_str("Invalid UTXO."))?);
+    }
+    return Ok(utxos);
+}
+
+/// Accumulator parameters used by the exported functions. Must match the parameters of the runtime.
+fn params() -> AccumulatorParams {
+    return AccumulatorParams::default();
 }
 
 #[wasm_bindgen]
 pub fn hash_to_prime(elem: &[u8]) -> Vec<u8> {
     let mut result: [u8; 256] = [0; 256];  // Change this constant
-    subroutines::hash_to_prime(elem).to_little_endian(&mut result);
+    subroutines::hash_to_prime(&params(), elem).to_little_endian(&mut result);
     return result.to_vec();
 }
 
+/// Returns the nonce at which the element hashes to a prime. Included in transactions so that the
+/// runtime only needs to run a single primality test.
+#[wasm_bindgen]
+pub fn get_prime_nonce(elem: &[u8]) -> u64 {
+    let (_, nonce) = subroutines::hash_to_prime_with_nonce(&params(), elem);
+    return nonce;
+}
+
+/// Converts an accumulator error into an exception on the JavaScript side.
+fn to_js_error(error: Error) -> JsValue {
+    return JsValue::from_str(error.as_str());
+}
+
 #[wasm_bindgen]
-pub fn get_witness(old_state: &[u8], agg: &[u8], elem: &[u8]) -> Vec<u8> {
+pub fn get_witness(old_state: &[u8], agg: &[u8], elem: &[u8]) -> Result<Vec<u8>, JsValue> {
     let mut result: [u8; 256] = [0; 256];
-    witnesses::mem_wit_create(U2048::from_little_endian(old_state),  U2048::from_little_endian(agg),
-                              U2048::from_little_endian(elem)).unwrap().to_little_endian(&mut result);
-    return result.to_vec();
+    witnesses::mem_wit_create(&params(), U2048::from_little_endian(old_state), &BigUint::from_little_endian(agg),
+                              &BigUint::from_little_endian(elem)).map_err(to_js_error)?.to_little_endian(&mut result);
+    return Ok(result.to_vec());
 }
 
 #[wasm_bindgen]
-pub fn update_witness(elem: &[u8], witness: &[u8], new_state: &[u8], added: &[u8], deleted: &[u8]) -> Vec<u8> {
+pub fn update_witness(elem: &[u8], witness: &[u8], new_state: &[u8], added: &[u8], deleted: &[u8]) -> Result<Vec<u8>, JsValue> {
     let mut result: [u8; 256] = [0; 256];
-    witnesses::update_mem_wit(U2048::from_little_endian(elem), U2048::from_little_endian(witness), U2048::from_little_endian(new_state),
-                              U2048::from_little_endian(added), U2048::from_little_endian(deleted)).to_little_endian(&mut result);
-    return result.to_vec();
+    witnesses::update_mem_wit(&params(), U2048::from_little_endian(elem), U2048::from_little_endian(witness), U2048::from_little_endian(new_state),
+                              &BigUint::from_little_endian(added), &BigUint::from_little_endian(deleted)).map_err(to_js_error)?.to_little_endian(&mut result);
+    return Ok(result.to_vec());
+}
+
+/// Encodes a value returned by the functions above as a minimal "0x" prefixed hex string.
+#[wasm_bindgen]
+pub fn to_hex(value: &[u8]) -> String {
+    return encoding::to_hex(&U2048::from_little_endian(value));
+}
+
+/// Inverse of to_hex.
+#[wasm_bindgen]
+pub fn from_hex(value: &str) -> Result<Vec<u8>, JsValue> {
+    let mut result: [u8; 256] = [0; 256];
+    encoding::from_hex(value).map_err(to_js_error)?.to_little_endian(&mut result);
+    return Ok(result.to_vec());
 }
 
 #[cfg(test)]
@@ -57,8 +109,9 @@ mod tests {
         let utxo = UTXO {
             pub_key: H256::from_slice(hex::decode("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d").unwrap()),
             id: 0,
+            amount: 100,
         };
-        assert_eq!(subroutines::hash_to_prime(&utxo.encode()), U2048::from_dec_str("2882671871935824533").unwrap());
+        assert_eq!(subroutines::hash_to_prime(&params(), &utxo.encode()), U2048::from_dec_str("301998823541452652982874991127539297113").unwrap());
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,387 @@
+/// Variable-Width Unsigned BigInts
+/// Arbitrary precision integers used for aggregated exponents(products of accumulated elements). A U2048 can
+/// only hold the product of a handful of realistically sized primes, so batches use this type instead.
+
+use codec::{Encode, Decode, Input};
+use core::cmp::Ordering;
+use core::ops::{Add, Mul, Sub};
+use rstd::prelude::Vec;
+use rstd::vec;
+use super::{Error, U2048};
+use crate::montgomery;
+
+/// Number of limbs in a U2048.
+const U2048_LIMBS: usize = 32;
+
+/// Unsigned integer stored as little endian 64 bit limbs. The most significant limb is never zero so that
+/// the derived equality and the encoding are canonical.
+#[derive(Encode, Clone, Eq, PartialEq, Debug, Default)]
+pub struct BigUint {
+    limbs: Vec<u64>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        return BigUint { limbs: Vec::new() };
+    }
+
+    pub fn one() -> Self {
+        return BigUint::from(U2048::from(1));
+    }
+
+    /// Creates an integer from little endian limbs, removing leading zero limbs.
+    fn from_limbs(mut limbs: Vec<u64>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        return BigUint { limbs };
+    }
+
+    /// Creates an integer from little endian bytes.
+    pub fn from_little_endian(bytes: &[u8]) -> Self {
+        let mut limbs = Vec::with_capacity((bytes.len() + 7) / 8);
+        for chunk in bytes.chunks(8) {
+            let mut limb = [0u8; 8];
+            limb[..chunk.len()].copy_from_slice(chunk);
+            limbs.push(u64::from_le_bytes(limb));
+        }
+        return BigUint::from_limbs(limbs);
+    }
+
+    /// Returns the little endian bytes of the integer. Zero is represented by an empty vector.
+    pub fn to_little_endian(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.limbs.iter().flat_map(|limb| limb.to_le_bytes().to_vec()).collect();
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        return bytes;
+    }
+
+    /// Little endian 64 bit limbs of the integer(see Montgomery::pow).
+    pub fn limbs(&self) -> &[u64] {
+        return &self.limbs;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.limbs.is_empty();
+    }
+
+    /// Returns the number of significant bits.
+    pub fn bits(&self) -> usize {
+        return montgomery::bit_length(&self.limbs);
+    }
+
+    /// Returns the bit at the given index(0 is the least significant bit).
+    pub fn bit(&self, index: usize) -> bool {
+        match self.limbs.get(index / 64) {
+            Some(limb) => {
+                return (limb >> (index % 64)) & 1 == 1;
+            },
+            None => {
+                return false;
+            },
+        }
+    }
+
+    /// Shifts the integer right by the given number of bits.
+    pub fn shr(&self, bits: usize) -> BigUint {
+        let limb_shift = bits / 64;
+        let bit_shift = bits % 64;
+        if limb_shift >= self.limbs.len() {
+            return BigUint::zero();
+        }
+        let mut limbs = self.limbs[limb_shift..].to_vec();
+        if bit_shift != 0 {
+            for i in 0..limbs.len() {
+                let high = if i + 1 < limbs.len() { limbs[i + 1] << (64 - bit_shift) } else { 0 };
+                limbs[i] = (limbs[i] >> bit_shift) | high;
+            }
+        }
+        return BigUint::from_limbs(limbs);
+    }
+
+    /// Converts the integer to a U2048. Fails if it does not fit.
+    pub fn to_u2048(&self) -> Result<U2048, Error> {
+        if self.limbs.len() > U2048_LIMBS {
+            return Err(Error::Overflow);
+        }
+        let mut limbs = [0u64; U2048_LIMBS];
+        limbs[..self.limbs.len()].copy_from_slice(&self.limbs);
+        return Ok(U2048(limbs));
+    }
+
+    /// Computes the quotient and the remainder of the division by a non-zero divisor using binary long
+    /// division. Panics if the divisor is zero.
+    pub fn div_rem(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        if divisor.is_zero() {
+            panic!("Division by zero");
+        }
+        if self < divisor {
+            return (BigUint::zero(), self.clone());
+        }
+
+        // The top bits of the dividend that are shorter than the divisor cannot produce quotient bits, so the
+        // remainder starts out with them.
+        let shift = self.bits() - divisor.bits();
+        let mut quotient = vec![0u64; shift / 64 + 1];
+        let mut remainder = self.shr(shift + 1).limbs;
+        remainder.reserve(2);
+        for i in (0..=shift).rev() {
+            shl_one(&mut remainder, self.bit(i));
+            if cmp_limbs(&remainder, &divisor.limbs) != Ordering::Less {
+                sub_assign_limbs(&mut remainder, &divisor.limbs);
+                quotient[i / 64] |= 1 << (i % 64);
+            }
+        }
+        return (BigUint::from_limbs(quotient), BigUint::from_limbs(remainder));
+    }
+
+    /// Divides by a U2048. The remainder always fits in a U2048.
+    pub fn div_rem_u2048(&self, divisor: U2048) -> (BigUint, U2048) {
+        let (quotient, remainder) = self.div_rem(&BigUint::from(divisor));
+        return (quotient, remainder.to_u2048().unwrap());
+    }
+
+    /// Computes the inverse modulo "modulus" in [0, modulus). Fails if the integer and the modulus are not
+    /// coprime. Together with the quotient (a*self - 1)/modulus, this gives Bezout coefficients for products
+    /// that are too wide for an I2048.
+    pub fn mod_inverse(&self, modulus: &BigUint) -> Result<BigUint, Error> {
+        // Extended Euclidean algorithm that only tracks the coefficient of self. Consecutive coefficients have
+        // opposite signs, so t_(i+1) = t_(i-1) - q*t_i is computed on magnitudes as |t_(i-1)| + q*|t_i|.
+        let (mut old_r, mut r) = (modulus.clone(), self.div_rem(modulus).1);
+        let (mut old_t, mut t) = (BigUint::zero(), BigUint::one());
+        let mut negative = false;
+        while !r.is_zero() {
+            let (q, new_r) = old_r.div_rem(&r);
+            old_r = r;
+            r = new_r;
+            let new_t = old_t + q * t.clone();
+            old_t = t;
+            t = new_t;
+            negative = !negative;
+        }
+        if old_r != BigUint::one() {
+            return Err(Error::NotCoprime);
+        }
+        // old_t has the opposite sign of t.
+        if !negative && !old_t.is_zero() {
+            return Ok(modulus.clone() - old_t);
+        }
+        return Ok(old_t);
+    }
+}
+
+/// Shifts little endian limbs left by one bit and sets the lowest bit.
+fn shl_one(limbs: &mut Vec<u64>, low_bit: bool) {
+    let mut carry = low_bit as u64;
+    for limb in limbs.iter_mut() {
+        let next_carry = *limb >> 63;
+        *limb = (*limb << 1) | carry;
+        carry = next_carry;
+    }
+    if carry != 0 {
+        limbs.push(carry);
+    }
+}
+
+/// Compares little endian limbs where neither has leading zero limbs.
+fn cmp_limbs(a: &[u64], b: &[u64]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    for i in (0..a.len()).rev() {
+        if a[i] != b[i] {
+            return a[i].cmp(&b[i]);
+        }
+    }
+    return Ordering::Equal;
+}
+
+/// Computes a -= b for a >= b and removes the resulting leading zero limbs.
+fn sub_assign_limbs(a: &mut Vec<u64>, b: &[u64]) {
+    let mut borrow = false;
+    for i in 0..a.len() {
+        let rhs = if i < b.len() { b[i] } else { 0 };
+        let (diff, borrow_1) = a[i].overflowing_sub(rhs);
+        let (diff, borrow_2) = diff.overflowing_sub(borrow as u64);
+        a[i] = diff;
+        borrow = borrow_1 || borrow_2;
+    }
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+}
+
+impl Decode for BigUint {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let limbs = Vec::<u64>::decode(input)?;
+        if limbs.last() == Some(&0) {
+            return Err("BigUint has leading zero limbs".into());
+        }
+        return Ok(BigUint { limbs });
+    }
+}
+
+impl From<U2048> for BigUint {
+    fn from(value: U2048) -> Self {
+        return BigUint::from_limbs(value.0.to_vec());
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        return cmp_limbs(&self.limbs, &other.limbs);
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= rhs.limbs.len() { (self, rhs) } else { (rhs, self) };
+        let mut result = long.limbs;
+        let mut carry = false;
+        for i in 0..result.len() {
+            let addend = if i < short.limbs.len() { short.limbs[i] } else { 0 };
+            let (sum, carry_1) = result[i].overflowing_add(addend);
+            let (sum, carry_2) = sum.overflowing_add(carry as u64);
+            result[i] = sum;
+            carry = carry_1 || carry_2;
+        }
+        if carry {
+            result.push(1);
+        }
+        return BigUint::from_limbs(result);
+    }
+}
+
+/// Panics if the result would be negative.
+impl Sub for BigUint {
+    type Output = BigUint;
+
+    fn sub(self, rhs: BigUint) -> BigUint {
+        if self < rhs {
+            panic!("Subtraction overflow");
+        }
+        let mut result = self.limbs;
+        sub_assign_limbs(&mut result, &rhs.limbs);
+        return BigUint { limbs: result };
+    }
+}
+
+/// Schoolbook multiplication.
+impl Mul for BigUint {
+    type Output = BigUint;
+
+    fn mul(self, rhs: BigUint) -> BigUint {
+        if self.is_zero() || rhs.is_zero() {
+            return BigUint::zero();
+        }
+        let mut result = vec![0u64; self.limbs.len() + rhs.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry: u64 = 0;
+            for (j, &b) in rhs.limbs.iter().enumerate() {
+                let t = result[i + j] as u128 + (a as u128) * (b as u128) + carry as u128;
+                result[i + j] = t as u64;
+                carry = (t >> 64) as u64;
+            }
+            result[i + rhs.limbs.len()] = carry;
+        }
+        return BigUint::from_limbs(result);
+    }
+}
+
+impl Mul<U2048> for BigUint {
+    type Output = BigUint;
+
+    fn mul(self, rhs: U2048) -> BigUint {
+        return self * BigUint::from(rhs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul() {
+        let a = BigUint::from(U2048::max_value());
+        let product = a.clone() * a.clone();
+        assert_eq!(product.bits(), 4096);
+        assert_eq!(product.limbs()[0], 1);
+        assert_eq!(product.to_u2048(), Err(Error::Overflow));
+        assert_eq!(BigUint::from(U2048::from(6)) * U2048::from(7), BigUint::from(U2048::from(42)));
+        assert_eq!(a * BigUint::zero(), BigUint::zero());
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let a = BigUint::from(U2048::max_value());
+        let b = BigUint::from(U2048::from(1) << 1500) * U2048::from(12345);
+        let c = U2048::from(987654321);
+        let n = a.clone() * b.clone() * c;
+
+        assert_eq!(n.div_rem(&a), (b.clone() * c, BigUint::zero()));
+        assert_eq!(n.div_rem(&b), (a.clone() * c, BigUint::zero()));
+        assert_eq!(n.div_rem_u2048(c), (a * b, U2048::from(0)));
+
+        let (q, r) = BigUint::from(U2048::from(1000)).div_rem_u2048(U2048::from(7));
+        assert_eq!((q, r), (BigUint::from(U2048::from(142)), U2048::from(6)));
+        assert_eq!(BigUint::from(U2048::from(3)).div_rem_u2048(U2048::from(7)), (BigUint::zero(), U2048::from(3)));
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let a = BigUint::from(U2048::max_value());
+        let sum = a.clone() + BigUint::one();
+        assert_eq!(sum.bits(), 2049);
+        assert_eq!(sum.clone() - BigUint::one(), a);
+        assert_eq!(sum.clone() - sum, BigUint::zero());
+        assert_eq!(BigUint::from(U2048::from(5)) + BigUint::zero(), BigUint::from(U2048::from(5)));
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        let m = BigUint::from(U2048::from(1000003));
+        let a = BigUint::from(U2048::from(12345));
+        let inverse = a.mod_inverse(&m).unwrap();
+        assert_eq!((a.clone() * inverse.clone()).div_rem(&m).1, BigUint::one());
+        assert_eq!(inverse < m, true);
+        assert_eq!(BigUint::from(U2048::from(6)).mod_inverse(&BigUint::from(U2048::from(9))), Err(Error::NotCoprime));
+        assert_eq!(BigUint::from(U2048::from(3)).mod_inverse(&BigUint::from(U2048::from(7))), Ok(BigUint::from(U2048::from(5))));
+
+        // Moduli wider than a U2048.
+        let m = BigUint::from(U2048::max_value()) * BigUint::from(U2048::max_value() - U2048::from(2));
+        let a = BigUint::from(U2048::from(1) << 1999) * U2048::from(7);
+        let inverse = a.mod_inverse(&m).unwrap();
+        assert_eq!((a * inverse).div_rem(&m).1, BigUint::one());
+    }
+
+    #[test]
+    fn test_shr() {
+        let a = BigUint::from(U2048::from(0xabcdef)) * BigUint::from(U2048::from(1) << 2000);
+        assert_eq!(a.shr(2000), BigUint::from(U2048::from(0xabcdef)));
+        assert_eq!(a.shr(2004), BigUint::from(U2048::from(0xabcde)));
+        assert_eq!(a.shr(64), BigUint::from(U2048::from(0xabcdef) << 1936));
+        assert_eq!(a.shr(2024), BigUint::zero());
+        assert_eq!(a.shr(0), a);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let a = BigUint::from(U2048::from(1) << 100);
+        assert_eq!(a.limbs().len(), 2);
+        assert_eq!(BigUint::decode(&mut &a.encode()[..]), Ok(a.clone()));
+        assert_eq!(BigUint::from_little_endian(&a.to_little_endian()), a);
+        assert_eq!(BigUint::from_little_endian(&[0, 1, 0, 0]), BigUint::from(U2048::from(256)));
+
+        // Leading zero limbs are rejected so that every value has a single encoding.
+        assert_eq!(BigUint::decode(&mut &vec![0u64, 1, 0].encode()[..]).is_err(), true);
+    }
+}
@@ -0,0 +1,320 @@
+/// Class Groups of Imaginary Quadratic Orders
+/// Elements are reduced binary quadratic forms (a, b, c) of a fixed negative discriminant D = b^2 - 4ac.
+/// The order of the group is believed to be hard to compute, and D can be derived from a public seed,
+/// so an accumulator over a class group requires no trusted setup.
+/// Algorithms follow chapter 5.4 of "A Course in Computational Algebraic Number Theory" (Cohen).
+/// NOTE: Intermediate values must fit in an I2048 so discriminants should be at most ~1024 bits.
+
+use codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use super::{Error, I2048, U2048, LAMBDA};
+use crate::group::Group;
+use crate::subroutines;
+
+/// Default size of a discriminant derived from a seed.
+pub const DISCRIMINANT_BITS: usize = 1024;
+
+/// A binary quadratic form ax^2 + bxy + cy^2.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct Form {
+    pub a: I2048,
+    pub b: I2048,
+    pub c: I2048,
+}
+
+/// The class group of discriminant D where D < 0 and D = 1 mod 8.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, Debug)]
+pub struct ClassGroup {
+    discriminant: I2048,
+    lambda: u32,
+}
+
+impl ClassGroup {
+    /// Creates the class group of the given discriminant. Fails unless D < 0 and D = 1 mod 8,
+    /// which guarantees that the form (2, 1, (1 - D)/8) exists.
+    pub fn new(discriminant: I2048) -> Result<Self, Error> {
+        if !discriminant.is_negative() || discriminant.rem_euclid(U2048::from(8)) != U2048::from(1) {
+            return Err(Error::InvalidParams);
+        }
+        return Ok(ClassGroup { discriminant, lambda: LAMBDA });
+    }
+
+    /// Derives a discriminant D = -p from a public seed where p is a prime of "bits" bits and
+    /// p = 7 mod 8. Anyone can rederive the group from the seed so no trusted party is needed.
+    pub fn from_seed(seed: &[u8], bits: usize) -> Self {
+        let mut counter: u64 = 0;
+        loop {
+            let candidate = subroutines::hash_to_bits(seed, counter, bits) | U2048::from(7);
+            if subroutines::is_prime(candidate) {
+                return ClassGroup { discriminant: -I2048::from(candidate), lambda: LAMBDA };
+            }
+            counter += 1;
+        }
+    }
+
+    /// Sets the bit size of the prime representatives used with this group. Fails if lambda is not between
+    /// 2 and 2048.
+    pub fn with_lambda(mut self, lambda: u32) -> Result<Self, Error> {
+        if !crate::valid_lambda(lambda) {
+            return Err(Error::InvalidParams);
+        }
+        self.lambda = lambda;
+        return Ok(self);
+    }
+
+    pub fn discriminant(&self) -> I2048 {
+        return self.discriminant;
+    }
+}
+
+impl Group for ClassGroup {
+    type Elem = Form;
+
+    /// The principal form (1, 1, (1 - D)/4).
+    fn identity(&self) -> Form {
+        let c = (I2048::one() - self.discriminant) / I2048::from(4);
+        return Form { a: I2048::one(), b: I2048::one(), c };
+    }
+
+    /// The form (2, 1, (1 - D)/8). Used as the initial accumulator state.
+    fn generator(&self) -> Form {
+        let c = (I2048::one() - self.discriminant) / I2048::from(8);
+        return reduce(Form { a: I2048::from(2), b: I2048::one(), c });
+    }
+
+    /// Checks that a form belongs to this group and is in reduced form. Elements received from
+    /// untrusted parties should be checked with this function before use.
+    fn is_valid(&self, f: &Form) -> bool {
+        if f.a <= I2048::zero() || !is_reduced(f) {
+            return false;
+        }
+        // b^2 - 4ac = D < 0 is checked as 4ac - b^2 = |D| with checked products since untrusted forms may be
+        // too large to multiply.
+        let b_squared = f.b.magnitude().checked_mul(f.b.magnitude());
+        let four_ac = f.a.magnitude().checked_mul(f.c.magnitude()).and_then(|ac| ac.checked_mul(U2048::from(4)));
+        match (b_squared, four_ac) {
+            (Some(b_squared), Some(four_ac)) => {
+                return four_ac.checked_sub(b_squared) == Some(self.discriminant.magnitude());
+            },
+            _ => {
+                return false;
+            },
+        }
+    }
+
+    /// Group operation(composition of forms). Algorithm 5.4.7 of Cohen.
+    fn op(&self, f_1: &Form, f_2: &Form) -> Form {
+        let (f_1, f_2) = if f_1.a > f_2.a { (f_2, f_1) } else { (f_1, f_2) };
+        let (a_1, b_1) = (f_1.a, f_1.b);
+        let (a_2, b_2, c_2) = (f_2.a, f_2.b, f_2.c);
+
+        let s = (b_1 + b_2) / I2048::from(2);
+        let n = b_2 - s;
+
+        // First Euclidean step
+        let (y_1, d) = if (a_2 % a_1).is_zero() {
+            (I2048::zero(), a_1)
+        } else {
+            let (d, u, _) = extended_gcd(a_2, a_1);
+            (u, d)
+        };
+
+        // Second Euclidean step
+        let (x_2, y_2, d_1) = if (s % d).is_zero() {
+            (I2048::zero(), -I2048::one(), d)
+        } else {
+            let (d_1, x_2, y_2) = extended_gcd(s, d);
+            (x_2, -y_2, d_1)
+        };
+
+        // Compose
+        let v_1 = a_1 / d_1;
+        let v_2 = a_2 / d_1;
+        let r = modulo(y_1 * y_2 * n - x_2 * c_2, v_1);
+        let b_3 = b_2 + I2048::from(2) * v_2 * r;
+        let a_3 = v_1 * v_2;
+        let c_3 = (c_2 * d_1 + r * (b_2 + v_2 * r)) / v_1;
+
+        return reduce(Form { a: a_3, b: b_3, c: c_3 });
+    }
+
+    /// Inverse of a form. (a, b, c) * (a, -b, c) is the principal form.
+    fn inverse(&self, f: &Form) -> Form {
+        return reduce(Form { a: f.a, b: -f.b, c: f.c });
+    }
+
+    /// Computes f^exp with square-and-multiply.
+    fn exp(&self, f: &Form, exp: U2048) -> Form {
+        let mut result = self.identity();
+        let mut base = *f;
+        let bits = exp.bits();
+        for i in 0..bits {
+            if exp.bit(i) {
+                result = self.op(&result, &base);
+            }
+            if i + 1 < bits {
+                base = self.op(&base, &base);
+            }
+        }
+        return result;
+    }
+
+    fn lambda(&self) -> u32 {
+        return self.lambda;
+    }
+}
+
+/// Checks whether |b| <= a <= c and b >= 0 if either inequality is an equality.
+pub fn is_reduced(f: &Form) -> bool {
+    if f.b.abs() > f.a || f.a > f.c {
+        return false;
+    }
+    if (f.b.abs() == f.a || f.a == f.c) && f.b.is_negative() {
+        return false;
+    }
+    return true;
+}
+
+/// Normalizes a form so that -a < b <= a. The result is an equivalent form.
+fn normalize(f: Form) -> Form {
+    if -f.a < f.b && f.b <= f.a {
+        return f;
+    }
+    let r = (f.a - f.b).div_floor(I2048::from(2) * f.a);
+    let b = f.b + I2048::from(2) * r * f.a;
+    let c = f.a * r * r + f.b * r + f.c;
+    return Form { a: f.a, b, c };
+}
+
+/// Reduces a positive definite form. Algorithm 5.4.2 of Cohen.
+pub fn reduce(mut f: Form) -> Form {
+    f = normalize(f);
+    while f.a > f.c {
+        f = normalize(Form { a: f.c, b: -f.b, c: f.a });
+    }
+    if f.a == f.c && f.b.is_negative() {
+        f.b = -f.b;
+    }
+    return f;
+}
+
+/// Returns x mod m in [0, m) for m > 0.
+fn modulo(x: I2048, m: I2048) -> I2048 {
+    return x - m * x.div_floor(m);
+}
+
+/// Extended Euclidean Algorithm over signed integers. Returns (d, x, y) with a*x + b*y = d = gcd(a, b) >= 0.
+fn extended_gcd(a: I2048, b: I2048) -> (I2048, I2048, I2048) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (I2048::one(), I2048::zero());
+    let (mut old_t, mut t) = (I2048::zero(), I2048::one());
+
+    while !r.is_zero() {
+        let quotient = old_r / r;
+        let new_r = old_r - quotient * r;
+        old_r = r;
+        r = new_r;
+
+        let new_s = old_s - quotient * s;
+        old_s = s;
+        s = new_s;
+
+        let new_t = old_t - quotient * t;
+        old_t = t;
+        t = new_t;
+    }
+
+    if old_r.is_negative() {
+        return (-old_r, -old_s, -old_t);
+    }
+    return (old_r, old_s, old_t);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(a: i64, b: i64, c: i64) -> Form {
+        return Form { a: I2048::from(a), b: I2048::from(b), c: I2048::from(c) };
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(ClassGroup::new(I2048::from(-23)).is_ok(), true);
+        assert_eq!(ClassGroup::new(I2048::from(-21)), Err(Error::InvalidParams));
+        assert_eq!(ClassGroup::new(I2048::from(17)), Err(Error::InvalidParams));
+
+        // No prime representatives of fewer than 2 or more than 2048 bits exist.
+        let group = ClassGroup::new(I2048::from(-23)).unwrap();
+        assert_eq!(group.with_lambda(256).map(|group| group.lambda()), Ok(256));
+        assert_eq!(group.with_lambda(1), Err(Error::InvalidParams));
+        assert_eq!(group.with_lambda(2049), Err(Error::InvalidParams));
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!(reduce(form(6, 1, 1)), form(1, 1, 6));
+        assert_eq!(reduce(form(3, -1, 2)), form(2, 1, 3));
+        assert_eq!(reduce(form(3, 1, 2)), form(2, -1, 3));
+        assert_eq!(reduce(form(8, 7, 2)), form(2, 1, 2));
+        assert_eq!(is_reduced(&form(2, -1, 3)), true);
+        assert_eq!(is_reduced(&form(3, 1, 2)), false);
+    }
+
+    #[test]
+    fn test_group_laws() {
+        // The class number of D = -23 is 3 and the class number of D = -47 is 5.
+        let group = ClassGroup::new(I2048::from(-23)).unwrap();
+        let g = group.generator();
+        assert_eq!(g, form(2, 1, 3));
+        assert_eq!(group.op(&g, &group.identity()), g);
+        assert_eq!(group.op(&g, &group.inverse(&g)), group.identity());
+        assert_eq!(group.op(&g, &g), form(2, -1, 3));
+        assert_eq!(group.exp(&g, U2048::from(3)), group.identity());
+
+        let group = ClassGroup::new(I2048::from(-47)).unwrap();
+        let g = group.generator();
+        assert_eq!(group.exp(&g, U2048::from(5)), group.identity());
+        assert_eq!(group.exp(&g, U2048::from(7)), group.exp(&g, U2048::from(2)));
+        assert_eq!(group.is_valid(&group.exp(&g, U2048::from(3))), true);
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let group = ClassGroup::from_seed(b"stateless-blockchain", 256);
+        assert_eq!(group, ClassGroup::from_seed(b"stateless-blockchain", 256));
+        assert_eq!(group.discriminant().magnitude().bits(), 256);
+        assert_eq!(ClassGroup::new(group.discriminant()), Ok(group));
+
+        let g = group.generator();
+        assert_eq!(group.is_valid(&g), true);
+        assert_eq!(group.is_valid(&form(2, 1, 3)), false);
+
+        // (g^x)^y = (g^y)^x and g^x * g^y = g^(x+y)
+        let x = U2048::from(65537);
+        let y = U2048::from(1000003);
+        let g_x = group.exp(&g, x);
+        let g_y = group.exp(&g, y);
+        assert_eq!(group.is_valid(&g_x), true);
+        assert_eq!(group.exp(&g_x, y), group.exp(&g_y, x));
+        assert_eq!(group.op(&g_x, &g_y), group.exp(&g, x + y));
+        assert_eq!(group.op(&g_x, &group.inverse(&g_x)), group.identity());
+    }
+
+    #[test]
+    fn test_decode_oversized() {
+        // Reduced forms whose products overflow are rejected instead of panicking.
+        let group = ClassGroup::from_seed(b"stateless-blockchain", 256);
+        for &bits in &[1000, 1500, 2047] {
+            let big = I2048::from(U2048::from(1) << bits);
+            let form = Form { a: big, b: I2048::zero(), c: big };
+            assert_eq!(group.decode_elem(&form.encode()), Err(Error::InvalidElement));
+        }
+        let max = I2048::from(U2048::max_value());
+        let form = Form { a: max, b: -max + I2048::one(), c: max };
+        assert_eq!(group.is_valid(&form), false);
+        assert_eq!(group.decode_elem(&group.generator().encode()), Ok(group.generator()));
+    }
+}
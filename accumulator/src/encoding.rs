@@ -0,0 +1,342 @@
+/// Text Encodings
+/// Decimal, hex and base64 representations of accumulator values along with the serde implementations that
+/// use them, so that RPCs, JSON chain specs and wallet files can carry accumulator values. Every encoding is
+/// minimal-length: encoders never emit leading zeros and decoders reject them so that each value has a single
+/// representation. Integers are serialized as "0x" prefixed hex strings by human readable formats and as
+/// big endian bytes by binary formats. The "decimal" and "base64" modules can be used with #[serde(with)]
+/// to pick another representation for a field.
+
+use std::fmt;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor};
+use super::{BigUint, Error, I2048, U2048};
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Number of bytes in a U2048.
+const U2048_BYTES: usize = 256;
+
+/// Returns the big endian bytes of the integer without leading zeros. Zero is represented by no bytes.
+pub fn to_bytes(value: &U2048) -> Vec<u8> {
+    let mut bytes = [0u8; U2048_BYTES];
+    value.to_big_endian(&mut bytes);
+    let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(U2048_BYTES);
+    return bytes[start..].to_vec();
+}
+
+/// Inverse of to_bytes.
+pub fn from_bytes(bytes: &[u8]) -> Result<U2048, Error> {
+    if bytes.first() == Some(&0) {
+        return Err(Error::InvalidEncoding);
+    }
+    if bytes.len() > U2048_BYTES {
+        return Err(Error::Overflow);
+    }
+    return Ok(U2048::from_big_endian(bytes));
+}
+
+pub fn to_decimal(value: &U2048) -> String {
+    return value.to_string();
+}
+
+pub fn from_decimal(s: &str) -> Result<U2048, Error> {
+    if s.is_empty() || (s.len() > 1 && s.starts_with('0')) || !s.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(Error::InvalidEncoding);
+    }
+    return U2048::from_dec_str(s).map_err(|_| Error::Overflow);
+}
+
+pub fn to_hex(value: &U2048) -> String {
+    return bytes_to_hex(&to_bytes(value));
+}
+
+pub fn from_hex(s: &str) -> Result<U2048, Error> {
+    return from_bytes(&hex_to_bytes(s)?);
+}
+
+pub fn to_base64(value: &U2048) -> String {
+    return bytes_to_base64(&to_bytes(value));
+}
+
+pub fn from_base64(s: &str) -> Result<U2048, Error> {
+    return from_bytes(&base64_to_bytes(s)?);
+}
+
+/// Encodes big endian bytes without leading zeros as a "0x" prefixed hex string without leading zeros.
+/// Zero is encoded as "0x0".
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return "0x0".to_string();
+    }
+    return format!("0x{}", digits);
+}
+
+/// Inverse of bytes_to_hex. Accepts upper and lower case digits.
+fn hex_to_bytes(s: &str) -> Result<Vec<u8>, Error> {
+    if !s.starts_with("0x") {
+        return Err(Error::InvalidEncoding);
+    }
+    let digits = &s[2..];
+    if digits == "0" {
+        return Ok(Vec::new());
+    }
+    if digits.is_empty() || digits.starts_with('0') {
+        return Err(Error::InvalidEncoding);
+    }
+
+    let nibbles = digits
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8).ok_or(Error::InvalidEncoding))
+        .collect::<Result<Vec<u8>, Error>>()?;
+    // An odd number of digits means the first byte only has a low nibble.
+    let mut bytes = Vec::with_capacity((nibbles.len() + 1) / 2);
+    let offset = nibbles.len() % 2;
+    if offset == 1 {
+        bytes.push(nibbles[0]);
+    }
+    for pair in nibbles[offset..].chunks(2) {
+        bytes.push((pair[0] << 4) | pair[1]);
+    }
+    return Ok(bytes);
+}
+
+/// Encodes bytes using the standard base64 alphabet with padding.
+fn bytes_to_base64(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            }
+            else {
+                result.push('=');
+            }
+        }
+    }
+    return result;
+}
+
+/// Inverse of bytes_to_base64. Rejects leading zero bytes as well as non-canonical padding bits.
+fn base64_to_bytes(s: &str) -> Result<Vec<u8>, Error> {
+    if s.len() % 4 != 0 {
+        return Err(Error::InvalidEncoding);
+    }
+    let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+    let chunks: Vec<&[u8]> = s.as_bytes().chunks(4).collect();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 || (padding > 0 && index + 1 != chunks.len()) {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut n: u32 = 0;
+        for c in chunk[..4 - padding].iter() {
+            let value = BASE64_ALPHABET.iter().position(|a| a == c).ok_or(Error::InvalidEncoding)?;
+            n = (n << 6) | value as u32;
+        }
+        n <<= 6 * padding as u32;
+        if n & ((1 << (8 * padding)) - 1) != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        for i in 0..3 - padding {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    if bytes.first() == Some(&0) {
+        return Err(Error::InvalidEncoding);
+    }
+    return Ok(bytes);
+}
+
+impl Serialize for U2048 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&to_hex(self));
+        }
+        return serializer.serialize_bytes(&to_bytes(self));
+    }
+}
+
+/// Accepts hex strings, decimal strings and big endian bytes.
+struct U2048Visitor;
+
+impl<'de> Visitor<'de> for U2048Visitor {
+    type Value = U2048;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        return formatter.write_str("a minimal hex or decimal string or big endian bytes");
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<U2048, E> {
+        let result = if s.starts_with("0x") { from_hex(s) } else { from_decimal(s) };
+        return result.map_err(|error| E::custom(error.as_str()));
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<U2048, E> {
+        return from_bytes(bytes).map_err(|error| E::custom(error.as_str()));
+    }
+}
+
+impl<'de> Deserialize<'de> for U2048 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<U2048, D::Error> {
+        if deserializer.is_human_readable() {
+            return deserializer.deserialize_str(U2048Visitor);
+        }
+        return deserializer.deserialize_bytes(U2048Visitor);
+    }
+}
+
+/// Signed integers are encoded as hex strings with an optional "-" sign in every format.
+impl Serialize for I2048 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sign = if self.is_negative() { "-" } else { "" };
+        return serializer.serialize_str(&format!("{}{}", sign, to_hex(&self.magnitude())));
+    }
+}
+
+impl<'de> Deserialize<'de> for I2048 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<I2048, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let negative = s.starts_with('-');
+        let magnitude = if negative { &s[1..] } else { &s[..] };
+        let magnitude = from_hex(magnitude).map_err(|error| de::Error::custom(error.as_str()))?;
+        if negative && magnitude.is_zero() {
+            return Err(de::Error::custom(Error::InvalidEncoding.as_str()));
+        }
+        return Ok(I2048::new(magnitude, negative));
+    }
+}
+
+impl Serialize for BigUint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = self.to_little_endian();
+        bytes.reverse();
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&bytes_to_hex(&bytes));
+        }
+        return serializer.serialize_bytes(&bytes);
+    }
+}
+
+impl<'de> Deserialize<'de> for BigUint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        let mut bytes = if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            hex_to_bytes(&s).map_err(|error| de::Error::custom(error.as_str()))?
+        }
+        else {
+            let bytes = Vec::<u8>::deserialize(deserializer)?;
+            if bytes.first() == Some(&0) {
+                return Err(de::Error::custom(Error::InvalidEncoding.as_str()));
+            }
+            bytes
+        };
+        bytes.reverse();
+        return Ok(BigUint::from_little_endian(&bytes));
+    }
+}
+
+/// Serializes a U2048 as a decimal string. Use with #[serde(with = "accumulator::encoding::decimal")].
+pub mod decimal {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &U2048, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(&to_decimal(value));
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U2048, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        return from_decimal(&s).map_err(|error| de::Error::custom(error.as_str()));
+    }
+}
+
+/// Serializes a U2048 as a base64 string. Use with #[serde(with = "accumulator::encoding::base64")].
+pub mod base64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &U2048, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(&to_base64(value));
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U2048, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        return from_base64(&s).map_err(|error| de::Error::custom(error.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::IntoDeserializer;
+    use serde::de::value::{Error as ValueError, StrDeserializer};
+
+    fn deserialize_str<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, ValueError> {
+        let deserializer: StrDeserializer<ValueError> = s.into_deserializer();
+        return T::deserialize(deserializer);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(to_hex(&U2048::from(0)), "0x0");
+        assert_eq!(to_hex(&U2048::from(0xabc)), "0xabc");
+        assert_eq!(to_hex(&(U2048::from(1) << 2047)), format!("0x8{}", "0".repeat(511)));
+        assert_eq!(from_hex("0xabc"), Ok(U2048::from(0xabc)));
+        assert_eq!(from_hex("0xABC"), Ok(U2048::from(0xabc)));
+        assert_eq!(from_hex("0x0"), Ok(U2048::from(0)));
+        assert_eq!(from_hex(&to_hex(&U2048::max_value())), Ok(U2048::max_value()));
+
+        // Leading zeros, missing prefixes, invalid digits and values that are too large
+        assert_eq!(from_hex("0x0abc"), Err(Error::InvalidEncoding));
+        assert_eq!(from_hex("0x"), Err(Error::InvalidEncoding));
+        assert_eq!(from_hex("abc"), Err(Error::InvalidEncoding));
+        assert_eq!(from_hex("0xabg"), Err(Error::InvalidEncoding));
+        assert_eq!(from_hex(&format!("0x1{}", "0".repeat(512))), Err(Error::Overflow));
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(to_decimal(&U2048::from(12345)), "12345");
+        assert_eq!(from_decimal("12345"), Ok(U2048::from(12345)));
+        assert_eq!(from_decimal("0"), Ok(U2048::from(0)));
+        assert_eq!(from_decimal(&to_decimal(&U2048::max_value())), Ok(U2048::max_value()));
+        assert_eq!(from_decimal("012345"), Err(Error::InvalidEncoding));
+        assert_eq!(from_decimal("-1"), Err(Error::InvalidEncoding));
+        assert_eq!(from_decimal(""), Err(Error::InvalidEncoding));
+        assert_eq!(from_decimal(&format!("1{}", "0".repeat(617))), Err(Error::Overflow));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(bytes_to_base64(b"foob"), "Zm9vYg==");
+        assert_eq!(bytes_to_base64(b"fooba"), "Zm9vYmE=");
+        assert_eq!(bytes_to_base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(to_base64(&U2048::from(0)), "");
+        assert_eq!(to_base64(&U2048::from(0x10001)), "AQAB");
+        assert_eq!(from_base64("AQAB"), Ok(U2048::from(0x10001)));
+        assert_eq!(from_base64(""), Ok(U2048::from(0)));
+        assert_eq!(from_base64(&to_base64(&U2048::max_value())), Ok(U2048::max_value()));
+
+        // Leading zero bytes, bad padding and non-canonical trailing bits
+        assert_eq!(from_base64("AAEAAQ=="), Err(Error::InvalidEncoding));
+        assert_eq!(from_base64("AQA"), Err(Error::InvalidEncoding));
+        assert_eq!(from_base64("AQ==AQAB"), Err(Error::InvalidEncoding));
+        assert_eq!(from_base64("AR=="), Err(Error::InvalidEncoding));
+        assert_eq!(from_base64("AQ*B"), Err(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert_eq!(deserialize_str::<U2048>("0xff").unwrap(), U2048::from(255));
+        assert_eq!(deserialize_str::<U2048>("255").unwrap(), U2048::from(255));
+        assert_eq!(deserialize_str::<U2048>("0x0ff").is_err(), true);
+        assert_eq!(deserialize_str::<I2048>("-0xff").unwrap(), I2048::from(-255));
+        assert_eq!(deserialize_str::<I2048>("0xff").unwrap(), I2048::from(255));
+        assert_eq!(deserialize_str::<I2048>("-0x0").is_err(), true);
+        assert_eq!(deserialize_str::<BigUint>("0x1ff").unwrap(), BigUint::from(U2048::from(0x1ff)));
+        assert_eq!(deserialize_str::<BigUint>("0x0").unwrap(), BigUint::zero());
+    }
+}
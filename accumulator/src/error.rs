@@ -0,0 +1,52 @@
+/// Accumulator Errors
+/// Errors returned by fallible accumulator operations. Runtime modules can convert them into dispatch
+/// errors with "?" since they implement Into<&'static str>.
+
+/// Reasons an accumulator operation can fail.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Error {
+    /// Two values that must be coprime share a factor(Ex: an element that is already accumulated).
+    NotCoprime,
+    /// A witness does not verify against the accumulator state.
+    InvalidWitness,
+    /// An element does not divide the aggregated product it is supposed to be a part of.
+    NotDivisible,
+    /// A product of elements does not fit in a U2048.
+    Overflow,
+    /// A value is not a valid group element.
+    InvalidElement,
+    /// A value does not hash to a prime at the given nonce.
+    NotPrime,
+    /// The parameters do not define a valid group.
+    InvalidParams,
+    /// An operation that requires at least one element was given none.
+    Empty,
+    /// A string or byte encoding is malformed or not minimal.
+    InvalidEncoding,
+    /// Inputs that must correspond one to one have different lengths.
+    LengthMismatch,
+}
+
+impl Error {
+    /// Returns a human readable description of the error.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Error::NotCoprime => "Values are not coprime.",
+            Error::InvalidWitness => "Witness is invalid.",
+            Error::NotDivisible => "Element does not divide the aggregated product.",
+            Error::Overflow => "Product of elements overflows.",
+            Error::InvalidElement => "Value is not a valid group element.",
+            Error::NotPrime => "Value does not hash to a prime at the given nonce.",
+            Error::InvalidParams => "Parameters do not define a valid group.",
+            Error::Empty => "No elements were given.",
+            Error::InvalidEncoding => "Encoding is invalid.",
+            Error::LengthMismatch => "Inputs have different lengths.",
+        }
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(error: Error) -> &'static str {
+        return error.as_str();
+    }
+}
@@ -0,0 +1,256 @@
+/// Groups of Unknown Order
+/// Abstraction over the hidden-order group that the accumulator, proofs and witnesses operate in.
+/// Implemented for RSA groups(AccumulatorParams) and class groups(ClassGroup).
+
+use codec::{Encode, Decode};
+use core::fmt::Debug;
+use rstd::prelude::Vec;
+use super::{AccumulatorParams, BigUint, Error, I2048, U2048};
+use crate::subroutines;
+
+/// A group of unknown order. The implementing type holds the parameters of the group while
+/// group elements are represented by "Elem". The encoded parameters are bound into every Fiat-Shamir
+/// transcript(see transcript). Groups and elements are shared across threads by the parallel subroutines.
+pub trait Group: Encode + Sync {
+    type Elem: Copy + Clone + Eq + PartialEq + Encode + Decode + Debug + Default + Send + Sync;
+
+    /// Identity element of the group.
+    fn identity(&self) -> Self::Elem;
+
+    /// Element that an empty accumulator is initialized with.
+    fn generator(&self) -> Self::Elem;
+
+    /// The group operation.
+    fn op(&self, a: &Self::Elem, b: &Self::Elem) -> Self::Elem;
+
+    /// Raises an element to a non-negative power.
+    fn exp(&self, base: &Self::Elem, exp: U2048) -> Self::Elem;
+
+    /// Inverse of an element.
+    fn inverse(&self, a: &Self::Elem) -> Self::Elem;
+
+    /// Checks that a value is a valid (canonical) element of the group.
+    fn is_valid(&self, a: &Self::Elem) -> bool;
+
+    /// Bit size of the prime representatives of accumulated elements(see subroutines::hash_to_prime).
+    fn lambda(&self) -> u32;
+
+    /// Raises an element to a possibly negative power.
+    fn exp_signed(&self, base: &Self::Elem, exp: I2048) -> Self::Elem {
+        if exp.is_negative() {
+            return self.exp(&self.inverse(base), exp.magnitude());
+        }
+        return self.exp(base, exp.magnitude());
+    }
+
+    /// Raises an element to a power of arbitrary width(Ex: the product of a batch of elements) with
+    /// square-and-multiply.
+    fn exp_big(&self, base: &Self::Elem, exp: &BigUint) -> Self::Elem {
+        let mut result = self.identity();
+        for i in (0..exp.bits()).rev() {
+            result = self.op(&result, &result);
+            if exp.bit(i) {
+                result = self.op(&result, base);
+            }
+        }
+        return result;
+    }
+
+    /// Computes bases[0]^exps[0] * ... * bases[n-1]^exps[n-1] with interleaved 4 bit windows(Straus' method)
+    /// so that the exponentiations share a single chain of squarings.
+    fn simultaneous_exp(&self, bases: &[Self::Elem], exps: &[BigUint]) -> Self::Elem {
+        const WINDOW: usize = 4;
+        // tables[i][d - 1] = bases[i]^d
+        let tables: Vec<Vec<Self::Elem>> = bases.iter().map(|base| {
+            let mut row = Vec::with_capacity((1 << WINDOW) - 1);
+            row.push(*base);
+            for d in 1..(1 << WINDOW) - 1 {
+                let next = self.op(&row[d - 1], base);
+                row.push(next);
+            }
+            return row;
+        }).collect();
+
+        let bits = exps.iter().map(|exp| exp.bits()).max().unwrap_or(0);
+        let mut result = self.identity();
+        for w in (0..(bits + WINDOW - 1) / WINDOW).rev() {
+            for _ in 0..WINDOW {
+                result = self.op(&result, &result);
+            }
+            for i in 0..bases.len() {
+                let mut digit = 0;
+                for j in (0..WINDOW).rev() {
+                    digit = (digit << 1) | exps[i].bit(w * WINDOW + j) as usize;
+                }
+                if digit != 0 {
+                    result = self.op(&result, &tables[i][digit - 1]);
+                }
+            }
+        }
+        return result;
+    }
+
+    /// Serializes an element.
+    fn encode_elem(&self, a: &Self::Elem) -> Vec<u8> {
+        return a.encode();
+    }
+
+    /// Deserializes an element. Fails if the bytes do not represent a valid element.
+    fn decode_elem(&self, bytes: &[u8]) -> Result<Self::Elem, Error> {
+        match Self::Elem::decode(&mut &bytes[..]) {
+            Ok(elem) if self.is_valid(&elem) => {
+                return Ok(elem);
+            },
+            _ => {
+                return Err(Error::InvalidElement);
+            },
+        }
+    }
+}
+
+/// The RSA group of integers modulo N. Elements are encoded as 256 little endian bytes.
+impl Group for AccumulatorParams {
+    type Elem = U2048;
+
+    fn identity(&self) -> U2048 {
+        return U2048::from(1);
+    }
+
+    fn generator(&self) -> U2048 {
+        return self.generator;
+    }
+
+    fn op(&self, a: &U2048, b: &U2048) -> U2048 {
+        match self.montgomery() {
+            Some(ctx) => {
+                return ctx.mul_mod(*a, *b);
+            },
+            None => {
+                return subroutines::mul_mod(*a, *b, self.modulus);
+            },
+        }
+    }
+
+    fn exp(&self, base: &U2048, exp: U2048) -> U2048 {
+        match self.montgomery() {
+            Some(ctx) => {
+                return ctx.pow(*base, &exp.0);
+            },
+            None => {
+                return subroutines::mod_exp(*base, exp, self.modulus);
+            },
+        }
+    }
+
+    fn exp_big(&self, base: &U2048, exp: &BigUint) -> U2048 {
+        match self.montgomery() {
+            Some(ctx) => {
+                return ctx.pow(*base, exp.limbs());
+            },
+            None => {
+                return subroutines::mod_exp_big(*base, exp, self.modulus);
+            },
+        }
+    }
+
+    fn simultaneous_exp(&self, bases: &[U2048], exps: &[BigUint]) -> U2048 {
+        match self.montgomery() {
+            Some(ctx) => {
+                let exps: Vec<&[u64]> = exps.iter().map(|exp| exp.limbs()).collect();
+                return ctx.simultaneous_pow(bases, &exps);
+            },
+            None => {
+                return bases.iter().zip(exps).fold(U2048::from(1), |result, (base, exp)| {
+                    return subroutines::mul_mod(result, subroutines::mod_exp_big(*base, exp, self.modulus), self.modulus);
+                });
+            },
+        }
+    }
+
+    fn inverse(&self, a: &U2048) -> U2048 {
+        return subroutines::mod_inverse(self, *a);
+    }
+
+    /// NOTE: Does not check that the element is coprime to the modulus.
+    fn is_valid(&self, a: &U2048) -> bool {
+        return !a.is_zero() && *a < self.modulus;
+    }
+
+    fn lambda(&self) -> u32 {
+        return self.lambda;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_group::ClassGroup;
+
+    #[test]
+    fn test_rsa_group() {
+        let group = AccumulatorParams::default();
+        let a = U2048::from(6);
+        assert_eq!(group.op(&a, &group.inverse(&a)), group.identity());
+        assert_eq!(group.exp_signed(&U2048::from(2), I2048::from(-7)), U2048::from(6));
+        assert_eq!(group.decode_elem(&group.encode_elem(&a)), Ok(a));
+        assert_eq!(group.decode_elem(&group.encode_elem(&U2048::from(13))), Err(Error::InvalidElement));
+        assert_eq!(group.decode_elem(&[1, 2, 3]), Err(Error::InvalidElement));
+
+        // Elements are encoded as raw little endian bytes.
+        let mut bytes: [u8; 256] = [0; 256];
+        a.to_little_endian(&mut bytes);
+        assert_eq!(group.encode_elem(&a), bytes.to_vec());
+
+        // No prime representatives of fewer than 2 or more than 2048 bits exist.
+        for &lambda in &[0, 1, 2049] {
+            assert_eq!(AccumulatorParams::new(group.modulus, group.generator, lambda), Err(Error::InvalidParams));
+        }
+        assert_eq!(AccumulatorParams::new(group.modulus, group.generator, 2).map(|params| params.lambda), Ok(2));
+    }
+
+    #[test]
+    fn test_simultaneous_exp() {
+        let params = AccumulatorParams::default();
+        let group = ClassGroup::from_seed(b"test_simultaneous_exp", 128);
+        let g = group.generator();
+        let bases = vec![g, group.exp(&g, U2048::from(3)), group.exp(&g, U2048::from(10))];
+        let exps = vec![BigUint::from(U2048::from(1) << 300), BigUint::from(U2048::from(12345)), BigUint::zero()];
+        let expected = bases.iter().zip(exps.iter()).fold(group.identity(), |acc, (base, exp)| group.op(&acc, &group.exp_big(base, exp)));
+        assert_eq!(group.simultaneous_exp(&bases, &exps), expected);
+        // 2^5 * 5^7 mod 13
+        assert_eq!(params.simultaneous_exp(&[U2048::from(2), U2048::from(5)], &[BigUint::from(U2048::from(5)), BigUint::from(U2048::from(7))]), U2048::from(9));
+        assert_eq!(params.simultaneous_exp(&[], &[]), U2048::from(1));
+
+        // Straus in Montgomery form for RSA groups and the fallback without a Montgomery context.
+        let params = AccumulatorParams::rsa_2048();
+        let bases = vec![params.generator, U2048::from(12345), U2048::from(3)];
+        let expected = bases.iter().zip(exps.iter()).fold(params.identity(), |acc, (base, exp)| params.op(&acc, &params.exp_big(base, exp)));
+        assert_eq!(params.simultaneous_exp(&bases, &exps), expected);
+        let even = AccumulatorParams::new(U2048::from(14), U2048::from(3), 2).unwrap();
+        assert_eq!(even.simultaneous_exp(&[U2048::from(3), U2048::from(5)], &[BigUint::from(U2048::from(5)), BigUint::from(U2048::from(7))]), U2048::from(11));
+    }
+
+    #[test]
+    fn test_montgomery_context() {
+        let params = AccumulatorParams::rsa_2048();
+        assert_eq!(params.montgomery().map(|ctx| ctx.modulus()), Some(params.modulus));
+        assert_eq!(AccumulatorParams::new(U2048::from(12), U2048::from(5), 128).unwrap().montgomery(), None);
+
+        // Only the public fields are encoded. Decoding rebuilds the context and checks lambda.
+        let mut bytes = params.encode();
+        assert_eq!(bytes.len(), 2 * 256 + 4);
+        let decoded = AccumulatorParams::decode(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded, params);
+        assert_eq!(decoded.montgomery(), params.montgomery());
+        let len = bytes.len();
+        bytes[len - 4..].copy_from_slice(&1u32.encode());
+        assert_eq!(AccumulatorParams::decode(&mut &bytes[..]).is_err(), true);
+
+        // A modulus changed after creation does not use the stale context.
+        let mut changed = params;
+        changed.modulus = U2048::from(23);
+        assert_eq!(changed.montgomery(), None);
+        assert_eq!(changed.exp(&U2048::from(2), U2048::from(11)), U2048::from(1));
+        assert_eq!(changed.op(&U2048::from(5), &U2048::from(14)), U2048::from(1));
+    }
+}
@@ -1,15 +1,36 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use codec::{Encode, Decode};
+use codec::{Encode, Decode, Input};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
 use rstd::vec::Vec;
 extern crate alloc;
 use alloc::borrow::ToOwned;
 #[macro_use]
 extern crate uint;
 
+pub mod error;
+pub mod biguint;
 pub mod subroutines;
+pub mod montgomery;
+pub mod signed;
+pub mod group;
+pub mod class_group;
+pub mod transcript;
 pub mod proofs;
 pub mod witnesses;
+pub mod multiset;
+pub mod precompute;
+#[cfg(feature = "std")]
+pub mod encoding;
+#[cfg(feature = "std")]
+pub mod manager;
+
+pub use error::Error;
+pub use biguint::BigUint;
+pub use signed::I2048;
+pub use group::Group;
+use montgomery::Montgomery;
 
 /// Construct BigInt type.
 construct_uint! {
@@ -21,68 +42,186 @@ construct_uint! {
 /// Example (insecure) modulus -> RSA 100: "1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139"
 pub const MODULUS: &str = "13";
 
-/// Security parameter that represents the size of elements added to the accumulator.
-pub const LAMBDA: u32 = u32::max_value();
+/// RSA-2048 challenge number from the RSA Factoring Challenge. Its factorization is not publicly known.
+pub const RSA_2048: &str = "25195908475657893494027183240048398571429282126204032027777137836043662020707595556264018525880784406918290641249515082189298559149176184502808489120072844992687392807287776735971418347270261896375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172654632282216869987549182422433637259085141865462043576798423387184774447920739934236584823824281198163815010674810451660377306056201619676256133844143603833904414952634432190114657544454178424020924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951378636564391212010397122822120720357";
+
+/// Default generator of the group. Assumed to have "unknown" order.
+pub const GENERATOR: u64 = 2;
+
+/// Security parameter that represents the bit size of the prime representatives of elements added to
+/// the accumulator. 128 bits gives 64 bits of collision resistance while 256 bits gives 128 bits.
+/// Must be between 2 and 2048.
+pub const LAMBDA: u32 = 128;
+
+/// Checks that prime representatives of "lambda" bits exist and fit in a U2048.
+pub(crate) fn valid_lambda(lambda: u32) -> bool {
+    return lambda >= 2 && lambda <= 2048;
+}
+
+/// Parameters of an accumulator instance. Every function that performs group operations takes
+/// these as an argument so that the same code can run with a small modulus in tests and an
+/// RSA-2048 modulus in production.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Clone, Copy, Eq, Debug)]
+pub struct AccumulatorParams {
+    /// RSA modulus that defines the group.
+    pub modulus: U2048,
+    /// Group element that the accumulator is initialized with.
+    pub generator: U2048,
+    /// Bit size of the prime representatives of elements added to the accumulator.
+    pub lambda: u32,
+    /// Montgomery context of the modulus, built once when the parameters are created so that group
+    /// operations do not redo the setup. Not part of the encoding.
+    #[codec(skip)]
+    #[cfg_attr(feature = "std", serde(skip))]
+    montgomery: Option<Montgomery>,
+}
+
+impl AccumulatorParams {
+    /// Fails if lambda is not between 2 and 2048 since no prime representatives of that size exist.
+    pub fn new(modulus: U2048, generator: U2048, lambda: u32) -> Result<Self, Error> {
+        if !valid_lambda(lambda) {
+            return Err(Error::InvalidParams);
+        }
+        return Ok(AccumulatorParams { modulus, generator, lambda, montgomery: Montgomery::new(modulus) });
+    }
+
+    /// Parameters using the RSA-2048 challenge number as the modulus.
+    pub fn rsa_2048() -> Self {
+        Self::new(U2048::from_dec_str(RSA_2048).unwrap(), U2048::from(GENERATOR), LAMBDA).unwrap()
+    }
+
+    /// Montgomery context of the modulus. None if the modulus is even or the context is missing because the
+    /// modulus was changed after creation or the parameters were deserialized with serde.
+    pub fn montgomery(&self) -> Option<&Montgomery> {
+        return self.montgomery.as_ref().filter(|ctx| ctx.modulus() == self.modulus);
+    }
+}
+
+/// Uses the (insecure) testing modulus.
+impl Default for AccumulatorParams {
+    fn default() -> Self {
+        Self::new(U2048::from_dec_str(MODULUS).unwrap(), U2048::from(GENERATOR), LAMBDA).unwrap()
+    }
+}
+
+/// The Montgomery context is derived from the modulus and does not take part in comparisons.
+impl PartialEq for AccumulatorParams {
+    fn eq(&self, other: &Self) -> bool {
+        return self.modulus == other.modulus && self.generator == other.generator && self.lambda == other.lambda;
+    }
+}
 
-/// A witness can either be a membership witness or a non-membership witness.
+/// Decodes the public fields and rebuilds the Montgomery context. Fails on an invalid lambda.
+impl Decode for AccumulatorParams {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let modulus = U2048::decode(input)?;
+        let generator = U2048::decode(input)?;
+        let lambda = u32::decode(input)?;
+        return AccumulatorParams::new(modulus, generator, lambda).map_err(|error| error.as_str().into());
+    }
+}
+
+/// A witness can either be a membership witness or a non-membership witness. "E" is the type of
+/// the group elements(U2048 for RSA groups).
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, Debug)]
-pub enum Witness {
-    MemWit(U2048),
-    NonMemWit((U2048,bool, U2048)),
+pub enum Witness<E = U2048> {
+    MemWit(E),
+    NonMemWit((I2048, E)),
+    AggNonMemWit(AggNonMemWit<E>),
 }
 
 // Cannot derive the Default trait for Enums so this is the only option
-impl Default for Witness {
+impl<E: Default> Default for Witness<E> {
     fn default() -> Self {
-        Witness::MemWit(U2048::from(0))
+        Witness::MemWit(E::default())
     }
 }
 
-/// A Bezout coefficient pair. This is a temporary workaround due to the lack of support for
-/// signed BigInts(coefficients can be negative).
+/// Constant-size non-membership proof for a product of elements(see witnesses::agg_non_mem_wit). Consists of
+/// V = state^a, the witness element B where V * B^x = old_state, and a NI-PoKE2 of a whose quotient is aggregated
+/// with the quotient of the NI-PoE for B using NI-PoKCR.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct AggNonMemWit<E = U2048> {
+    pub v: E,
+    pub b: E,
+    /// Commitment g^a of the NI-PoKE2.
+    pub z: E,
+    /// Residue of a modulo the NI-PoKE2 challenge.
+    pub r: U2048,
+    /// Product of the NI-PoKE2 and NI-PoE quotients.
+    pub q: E,
+}
+
+/// A Bezout coefficient pair (a, b) such that a*x + b*y = gcd(x, y).
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct BezoutPair {
-    coefficient_a: U2048,
-    coefficient_b: U2048,
-    sign_a: bool, // True indicates negative and false indicates positive
-    sign_b: bool,
+    coefficient_a: I2048,
+    coefficient_b: I2048,
 }
 
 /// Add a single element to an accumulator.
-pub fn add(state: U2048, elem: U2048) -> U2048 {
-    return subroutines::mod_exp(state, elem, U2048::from_dec_str(MODULUS).unwrap());
+pub fn add<G: Group>(group: &G, state: G::Elem, elem: U2048) -> G::Elem {
+    return group.exp(&state, elem);
 }
 
 /// Delete an element from the accumulator given a membership proof.
-pub fn delete(state: U2048, elem: U2048, proof: U2048) -> Option<U2048> {
-    if subroutines::mod_exp(proof, elem, U2048::from_dec_str(MODULUS).unwrap()) == state {
-        return Some(proof);
+pub fn delete<G: Group>(group: &G, state: G::Elem, elem: U2048, proof: G::Elem) -> Result<G::Elem, Error> {
+    if group.exp(&proof, elem) == state {
+        return Ok(proof);
     }
-    return None;
+    return Err(Error::InvalidWitness);
 }
 
 /// Aggregates a set of accumulator elements + witnesses and batch deletes them from the accumulator.
 /// Returns the state after deletion, the product of the deleted elements, and a proof of exponentiation.
-pub fn batch_delete(state: U2048, elems: &Vec<(U2048, U2048)>) -> (U2048, U2048, U2048) {
-    let (mut x_agg, mut new_state) = elems[0];
+/// Fails if any witness is invalid or if an element is deleted twice.
+pub fn batch_delete<G: Group>(group: &G, state: G::Elem, elems: &Vec<(U2048, G::Elem)>) -> Result<(G::Elem, BigUint, G::Elem), Error> {
+    if elems.is_empty() {
+        return Err(Error::Empty);
+    }
+    let (x, mut new_state) = elems[0];
+    let mut x_agg = BigUint::from(x);
     for i in 1..elems.len() {
         let (x, witness) = elems[i];
-        new_state = subroutines::shamir_trick(new_state, witness, x_agg, x).unwrap();
-        x_agg *= x;
+        new_state = subroutines::shamir_trick(group, new_state, witness, &x_agg, x)?;
+        x_agg = x_agg * x;
     }
-    let proof = proofs::poe(new_state, x_agg, state);
-    return (new_state, x_agg, proof);
+    // shamir_trick only checks the witnesses against each other so the result is checked against the state.
+    if group.exp_big(&new_state, &x_agg) != state {
+        return Err(Error::InvalidWitness);
+    }
+    let proof = proofs::poe(group, new_state, &x_agg, state);
+    return Ok((new_state, x_agg, proof));
 }
 
 /// Aggregates a set of accumulator elements + witnesses and batch adds them to the accumulator.
 /// Returns the state after addition, the product of the added elements, and a proof of exponentiation.
-pub fn batch_add(state: U2048, elems: &Vec<U2048>) -> (U2048, U2048, U2048) {
-    let mut x_agg = U2048::from(1);
-    for i in 0..elems.len() {
-        x_agg *= elems[i];
+pub fn batch_add<G: Group>(group: &G, state: G::Elem, elems: &Vec<U2048>) -> (G::Elem, BigUint, G::Elem) {
+    let x_agg = subroutines::prime_product(elems);
+    let new_state = group.exp_big(&state, &x_agg);
+    let proof = proofs::poe(group, state, &x_agg, new_state);
+    return (new_state, x_agg, proof);
+}
+
+/// Merges two accumulators that were built from the generator of the group(Ex: two shards or two epochs).
+/// Returns the union state state_x^agg_y and a NI-PoKE2 (Q, r) of agg_y where state_y serves as the
+/// commitment g^agg_y, so the verifier only needs the three states. The sets are assumed to be disjoint;
+/// elements contained in both accumulators appear twice in the union(see multiset).
+pub fn union<G: Group>(group: &G, state_x: G::Elem, state_y: G::Elem, agg_y: &BigUint) -> Result<(G::Elem, (G::Elem, U2048)), Error> {
+    let union = group.exp_big(&state_x, agg_y);
+    let (z, Q, r) = proofs::poke2_big(group, state_x, agg_y, union);
+    if z != state_y {
+        return Err(Error::InvalidWitness);
     }
+    return Ok((union, (Q, r)));
+}
 
-    let new_state = subroutines::mod_exp(state, x_agg, U2048::from_dec_str(MODULUS).unwrap());
-    let proof = proofs::poe(state, x_agg, new_state);
-    return (new_state, x_agg, proof);
+/// Verifies that "union" is the union of the accumulators state_x and state_y.
+pub fn verify_union<G: Group>(group: &G, state_x: G::Elem, state_y: G::Elem, union: G::Elem, proof: (G::Elem, U2048)) -> bool {
+    let (Q, r) = proof;
+    return proofs::verify_poke2(group, state_x, union, state_y, Q, r);
 }
@@ -0,0 +1,193 @@
+/// Trapdoor Accumulator Manager
+/// A trusted issuer that knows the factorization of the modulus(Ex: the operator of a permissioned
+/// revocation list) can compute roots in the RSA group directly since it knows phi(N). The manager
+/// deletes elements without witnesses, creates any membership witness with a single exponentiation and
+/// issues non-membership witnesses. Exponents are reduced modulo phi(N) so that the cost does not grow
+/// with the size of the set. The resulting states, witnesses and proofs are checked with the public
+/// verification functions. Only available with the std feature since phi(N) must never reach the runtime.
+
+use super::{AccumulatorParams, BigUint, Error, Group, I2048, U2048};
+use crate::{proofs, subroutines};
+
+/// Accumulator state together with the trapdoor phi(N) and the product of the accumulated elements.
+/// NOTE: Deliberately does not implement Debug or any encoding so that phi(N) is not leaked by accident.
+#[derive(Clone)]
+pub struct AccumulatorManager {
+    params: AccumulatorParams,
+    phi: U2048,
+    state: U2048,
+    agg: BigUint,
+}
+
+impl AccumulatorManager {
+    /// Creates an empty accumulator from the prime factors p and q of the modulus. Fails if they do not
+    /// multiply to the modulus.
+    pub fn new(params: AccumulatorParams, p: U2048, q: U2048) -> Result<Self, Error> {
+        let one = U2048::from(1);
+        if p <= one || q <= one || p.checked_mul(q) != Some(params.modulus) {
+            return Err(Error::InvalidParams);
+        }
+        return Ok(AccumulatorManager {
+            params,
+            phi: (p - one) * (q - one),
+            state: params.generator,
+            agg: BigUint::one(),
+        });
+    }
+
+    pub fn params(&self) -> &AccumulatorParams {
+        return &self.params;
+    }
+
+    /// Current state of the accumulator.
+    pub fn state(&self) -> U2048 {
+        return self.state;
+    }
+
+    /// Product of the accumulated elements.
+    pub fn agg(&self) -> &BigUint {
+        return &self.agg;
+    }
+
+    /// Adds a single element to the accumulator and returns the new state.
+    pub fn add(&mut self, elem: U2048) -> U2048 {
+        self.state = self.params.exp(&self.state, elem);
+        self.agg = self.agg.clone() * elem;
+        return self.state;
+    }
+
+    /// Batch adds a set of elements. Returns the same state, product and proof of exponentiation as
+    /// crate::batch_add.
+    pub fn batch_add(&mut self, elems: &[U2048]) -> (U2048, BigUint, U2048) {
+        let x_agg = subroutines::prime_product(elems);
+        let old_state = self.state;
+        self.state = self.params.exp(&old_state, self.reduce(&x_agg));
+        self.agg = self.agg.clone() * x_agg.clone();
+        let proof = self.poe(old_state, &x_agg, self.state);
+        return (self.state, x_agg, proof);
+    }
+
+    /// Deletes an accumulated element without a witness and returns the new state.
+    pub fn delete(&mut self, elem: U2048) -> Result<U2048, Error> {
+        let (agg, remainder) = self.agg.div_rem_u2048(elem);
+        if !remainder.is_zero() {
+            return Err(Error::NotDivisible);
+        }
+        self.state = self.root(self.state, &BigUint::from(elem))?;
+        self.agg = agg;
+        return Ok(self.state);
+    }
+
+    /// Batch deletes a set of accumulated elements without witnesses. Returns the same state, product and
+    /// proof of exponentiation as crate::batch_delete.
+    pub fn batch_delete(&mut self, elems: &[U2048]) -> Result<(U2048, BigUint, U2048), Error> {
+        if elems.is_empty() {
+            return Err(Error::Empty);
+        }
+        let x_agg = subroutines::prime_product(elems);
+        let (agg, remainder) = self.agg.div_rem(&x_agg);
+        if !remainder.is_zero() {
+            return Err(Error::NotDivisible);
+        }
+        let old_state = self.state;
+        self.state = self.root(old_state, &x_agg)?;
+        self.agg = agg;
+        let proof = self.poe(self.state, &x_agg, old_state);
+        return Ok((self.state, x_agg, proof));
+    }
+
+    /// Creates a membership witness for an accumulated element with a single exponentiation.
+    pub fn mem_wit_create(&self, elem: U2048) -> Result<U2048, Error> {
+        let (_, remainder) = self.agg.div_rem_u2048(elem);
+        if !remainder.is_zero() {
+            return Err(Error::NotDivisible);
+        }
+        return self.root(self.state, &BigUint::from(elem));
+    }
+
+    /// Creates a non-membership witness for an element relative to the generator, i.e. one that is checked
+    /// by witnesses::verify_non_mem_wit with the generator as "old_state". Fails if the element is accumulated.
+    pub fn non_mem_wit_create(&self, elem: U2048) -> Result<(I2048, U2048), Error> {
+        // a*agg + b*elem = 1, so B = g^b = (g * state^(-a))^(1/elem).
+        let (_, remainder) = self.agg.div_rem_u2048(elem);
+        let a = subroutines::bezout(remainder, elem)?.coefficient_a;
+        let g_b = self.params.op(&self.params.generator, &self.params.exp_signed(&self.state, -a));
+        return Ok((a, self.root(g_b, &BigUint::from(elem))?));
+    }
+
+    /// Reduces an exponent modulo phi(N).
+    fn reduce(&self, exp: &BigUint) -> U2048 {
+        let (_, remainder) = exp.div_rem(&BigUint::from(self.phi));
+        return remainder.to_u2048().unwrap();
+    }
+
+    /// Computes the xth root of an element. Fails if x is not invertible modulo phi(N).
+    fn root(&self, base: U2048, x: &BigUint) -> Result<U2048, Error> {
+        let inverse = subroutines::bezout(self.reduce(x), self.phi)?.coefficient_a.rem_euclid(self.phi);
+        return Ok(self.params.exp(&base, inverse));
+    }
+
+    /// Proof of exponentiation that u^x = w(see proofs::poe) with the quotient reduced modulo phi(N).
+    fn poe(&self, u: U2048, x: &BigUint, w: U2048) -> U2048 {
+        let l = proofs::poe_challenge(&self.params, u, x, w);
+        let (q, _) = x.div_rem_u2048(l);
+        return self.params.exp(&u, self.reduce(&q));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witnesses;
+
+    const RSA_100: &str = "1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139";
+    const RSA_100_P: &str = "37975227936943673922808872755445627854565536638199";
+    const RSA_100_Q: &str = "40094690950920881030683735292761468389214899724061";
+
+    fn manager() -> AccumulatorManager {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        return AccumulatorManager::new(params, U2048::from_dec_str(RSA_100_P).unwrap(), U2048::from_dec_str(RSA_100_Q).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_new() {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        let p = U2048::from_dec_str(RSA_100_P).unwrap();
+        assert_eq!(AccumulatorManager::new(params, p, p).err(), Some(Error::InvalidParams));
+        assert_eq!(AccumulatorManager::new(params, params.modulus, U2048::from(1)).err(), Some(Error::InvalidParams));
+    }
+
+    #[test]
+    fn test_manager() {
+        let mut manager = manager();
+        let params = *manager.params();
+        let elems: Vec<U2048> = (0..5).map(|i| subroutines::hash_to_prime(&params, &[i])).collect();
+
+        // Additions match the public functions and their proofs verify.
+        let (state, agg, proof) = manager.batch_add(&elems[..4]);
+        assert_eq!((state, agg.clone(), proof), crate::batch_add(&params, params.generator, &elems[..4].to_vec()));
+        assert_eq!(manager.add(elems[4]), crate::add(&params, state, elems[4]));
+        let state = manager.state();
+
+        // Membership witnesses for any element.
+        let witness = manager.mem_wit_create(elems[1]).unwrap();
+        assert_eq!(witnesses::verify_mem_wit(&params, state, witness, elems[1]), true);
+        assert_eq!(manager.mem_wit_create(U2048::from(7)), Err(Error::NotDivisible));
+
+        // Non-membership witnesses relative to the generator.
+        let outsider = subroutines::hash_to_prime(&params, &[9]);
+        let non_mem_wit = manager.non_mem_wit_create(outsider).unwrap();
+        assert_eq!(witnesses::verify_non_mem_wit(&params, params.generator, state, non_mem_wit, outsider), true);
+        assert_eq!(manager.non_mem_wit_create(elems[2]), Err(Error::NotCoprime));
+
+        // Deletions without witnesses.
+        let new_state = manager.delete(elems[1]).unwrap();
+        assert_eq!(Ok(new_state), crate::delete(&params, state, elems[1], witness));
+        assert_eq!(manager.delete(elems[1]), Err(Error::NotDivisible));
+        let (newer_state, deleted, proof) = manager.batch_delete(&[elems[0], elems[3]]).unwrap();
+        assert_eq!(proofs::verify_poe(&params, newer_state, &deleted, new_state, proof), true);
+        assert_eq!(manager.agg(), &(BigUint::from(elems[2]) * elems[4]));
+        assert_eq!(newer_state, params.exp(&params.exp(&params.generator, elems[2]), elems[4]));
+        assert_eq!(manager.batch_delete(&[]), Err(Error::Empty));
+    }
+}
@@ -0,0 +1,329 @@
+/// Montgomery Modular Arithmetic
+/// Multiplication and exponentiation modulo an odd modulus without any intermediate division.
+/// See https://en.wikipedia.org/wiki/Montgomery_modular_multiplication.
+
+use rstd::prelude::Vec;
+use super::U2048;
+
+/// Number of 64 bit limbs in a U2048.
+const LIMBS: usize = 32;
+
+/// Upper bound on the sliding window size used for exponentiation.
+const MAX_WINDOW: usize = 6;
+
+/// Window size of simultaneous exponentiation.
+const SIMULTANEOUS_WINDOW: usize = 4;
+
+/// Precomputed context for Montgomery multiplication modulo a fixed odd modulus. Values in
+/// "Montgomery form" are represented as aR mod N where R = 2^(64 * limbs).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Montgomery {
+    modulus: U2048,
+    /// Number of significant limbs of the modulus. Arithmetic only iterates over these limbs.
+    limbs: usize,
+    /// -N^(-1) mod 2^64.
+    n_prime: u64,
+    /// R mod N (Montgomery form of 1).
+    one: U2048,
+    /// R^2 mod N. Used to convert values into Montgomery form.
+    r_squared: U2048,
+}
+
+impl Montgomery {
+    /// Creates a context for the given modulus. Returns None if the modulus is even or smaller
+    /// than 3 since Montgomery reduction requires gcd(N, R) = 1.
+    pub fn new(modulus: U2048) -> Option<Self> {
+        if modulus < U2048::from(3) || modulus.low_u64() & 1 == 0 {
+            return None;
+        }
+
+        let limbs = (modulus.bits() + 63) / 64;
+
+        // Newton iteration for N^(-1) mod 2^64. Each step doubles the number of correct bits.
+        let n_0 = modulus.0[0];
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(n_0.wrapping_mul(inv)));
+        }
+
+        // R mod N and R^2 mod N are computed by repeated doubling starting from 1.
+        let mut one = U2048::from(1);
+        for _ in 0..(64 * limbs) {
+            one = double_mod(one, modulus);
+        }
+        let mut r_squared = one;
+        for _ in 0..(64 * limbs) {
+            r_squared = double_mod(r_squared, modulus);
+        }
+
+        return Some(Montgomery {
+            modulus,
+            limbs,
+            n_prime: inv.wrapping_neg(),
+            one,
+            r_squared,
+        });
+    }
+
+    /// Returns the modulus of the context.
+    pub fn modulus(&self) -> U2048 {
+        return self.modulus;
+    }
+
+    /// Converts a value into Montgomery form.
+    pub fn to_montgomery(&self, a: U2048) -> U2048 {
+        return self.mul(self.reduce(a), self.r_squared);
+    }
+
+    /// Reduces a value modulo N. Skips the division for values that are already reduced(Ex: group elements).
+    fn reduce(&self, a: U2048) -> U2048 {
+        if a < self.modulus {
+            return a;
+        }
+        return a % self.modulus;
+    }
+
+    /// Converts a value out of Montgomery form.
+    pub fn from_montgomery(&self, a: U2048) -> U2048 {
+        return self.mul(a, U2048::from(1));
+    }
+
+    /// Montgomery multiplication(CIOS variant). Given aR and bR (both less than N), returns abR mod N.
+    pub fn mul(&self, a: U2048, b: U2048) -> U2048 {
+        let n = &self.modulus.0;
+        let k = self.limbs;
+        let mut t = [0u64; LIMBS + 2];
+
+        for i in 0..k {
+            // t += a * b[i]
+            let mut carry = 0u64;
+            for j in 0..k {
+                let (lo, hi) = mac(t[j], a.0[j], b.0[i], carry);
+                t[j] = lo;
+                carry = hi;
+            }
+            let (sum, overflow) = t[k].overflowing_add(carry);
+            t[k] = sum;
+            t[k + 1] = overflow as u64;
+
+            // t = (t + m * N) / 2^64 where m is chosen so that the lowest limb becomes zero.
+            let m = t[0].wrapping_mul(self.n_prime);
+            let (_, mut carry) = mac(t[0], m, n[0], 0);
+            for j in 1..k {
+                let (lo, hi) = mac(t[j], m, n[j], carry);
+                t[j - 1] = lo;
+                carry = hi;
+            }
+            let (sum, overflow) = t[k].overflowing_add(carry);
+            t[k - 1] = sum;
+            t[k] = t[k + 1] + overflow as u64;
+        }
+
+        // The result is less than 2N and may carry into limb k.
+        let mut result = U2048::from(0);
+        result.0[..k].copy_from_slice(&t[..k]);
+        if k < LIMBS {
+            result.0[k] = t[k];
+        }
+        if (k == LIMBS && t[k] != 0) || result >= self.modulus {
+            result = result.overflowing_sub(self.modulus).0;
+        }
+        return result;
+    }
+
+    /// Computes a * b mod N for values in normal form.
+    pub fn mul_mod(&self, a: U2048, b: U2048) -> U2048 {
+        let product = self.mul(self.reduce(a), self.reduce(b));
+        return self.mul(product, self.r_squared);
+    }
+
+    /// Computes base^exp mod N using sliding window exponentiation. The exponent is given as little
+    /// endian 64 bit limbs so that exponents wider than the modulus are supported.
+    pub fn pow(&self, base: U2048, exp: &[u64]) -> U2048 {
+        let bits = bit_length(exp);
+        if bits == 0 {
+            return self.from_montgomery(self.one);
+        }
+
+        let window = match bits {
+            0..=8 => 1,
+            9..=64 => 3,
+            65..=256 => 4,
+            257..=1024 => 5,
+            _ => MAX_WINDOW,
+        };
+
+        // Precompute the odd powers base^1, base^3, ..., base^(2^window - 1).
+        let mut table = [U2048::from(0); 1 << (MAX_WINDOW - 1)];
+        table[0] = self.to_montgomery(base);
+        let base_squared = self.mul(table[0], table[0]);
+        for i in 1..(1 << (window - 1)) {
+            table[i] = self.mul(table[i - 1], base_squared);
+        }
+
+        let mut result = self.one;
+        let mut i = bits as isize - 1;
+        while i >= 0 {
+            if !bit(exp, i as usize) {
+                result = self.mul(result, result);
+                i -= 1;
+                continue;
+            }
+
+            // Find the longest window ending in a set bit.
+            let mut j = if i + 1 >= window as isize { i + 1 - window as isize } else { 0 };
+            while !bit(exp, j as usize) {
+                j += 1;
+            }
+
+            let mut value = 0usize;
+            for k in (j..=i).rev() {
+                result = self.mul(result, result);
+                value = (value << 1) | bit(exp, k as usize) as usize;
+            }
+            result = self.mul(result, table[value >> 1]);
+            i = j - 1;
+        }
+
+        return self.from_montgomery(result);
+    }
+
+    /// Computes bases[0]^exps[0] * ... * bases[n-1]^exps[n-1] mod N with interleaved 4 bit windows(Straus'
+    /// method). All intermediate values stay in Montgomery form, so each step costs a single multiplication.
+    pub fn simultaneous_pow(&self, bases: &[U2048], exps: &[&[u64]]) -> U2048 {
+        // tables[i][d - 1] = bases[i]^d in Montgomery form.
+        let tables: Vec<Vec<U2048>> = bases.iter().map(|base| {
+            let mut row = Vec::with_capacity((1 << SIMULTANEOUS_WINDOW) - 1);
+            row.push(self.to_montgomery(*base));
+            for d in 1..(1 << SIMULTANEOUS_WINDOW) - 1 {
+                let next = self.mul(row[d - 1], row[0]);
+                row.push(next);
+            }
+            return row;
+        }).collect();
+
+        let bits = exps.iter().map(|exp| bit_length(exp)).max().unwrap_or(0);
+        let mut result = self.one;
+        for w in (0..(bits + SIMULTANEOUS_WINDOW - 1) / SIMULTANEOUS_WINDOW).rev() {
+            for _ in 0..SIMULTANEOUS_WINDOW {
+                result = self.mul(result, result);
+            }
+            for i in 0..bases.len() {
+                let mut digit = 0;
+                for j in (0..SIMULTANEOUS_WINDOW).rev() {
+                    let index = w * SIMULTANEOUS_WINDOW + j;
+                    digit = (digit << 1) | (index < 64 * exps[i].len() && bit(exps[i], index)) as usize;
+                }
+                if digit != 0 {
+                    result = self.mul(result, tables[i][digit - 1]);
+                }
+            }
+        }
+        return self.from_montgomery(result);
+    }
+}
+
+/// Computes a + b * c + carry and returns the (low, high) limbs of the result.
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let result = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    return (result as u64, (result >> 64) as u64);
+}
+
+/// Computes 2a mod N for a < N without overflowing.
+fn double_mod(a: U2048, modulus: U2048) -> U2048 {
+    let (doubled, overflow) = a.overflowing_add(a);
+    if overflow || doubled >= modulus {
+        return doubled.overflowing_sub(modulus).0;
+    }
+    return doubled;
+}
+
+/// Returns the number of significant bits in a little endian limb slice.
+pub fn bit_length(limbs: &[u64]) -> usize {
+    for i in (0..limbs.len()).rev() {
+        if limbs[i] != 0 {
+            return 64 * i + 64 - limbs[i].leading_zeros() as usize;
+        }
+    }
+    return 0;
+}
+
+/// Returns the bit at the given position of a little endian limb slice.
+fn bit(limbs: &[u64], index: usize) -> bool {
+    return (limbs[index / 64] >> (index % 64)) & 1 == 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert_eq!(Montgomery::new(U2048::from(12)), None);
+        assert_eq!(Montgomery::new(U2048::from(1)), None);
+        assert_eq!(Montgomery::new(U2048::from(13)).unwrap().limbs, 1);
+        assert_eq!(Montgomery::new(U2048::max_value()).unwrap().limbs, 32);
+    }
+
+    #[test]
+    fn test_mul_mod() {
+        let ctx = Montgomery::new(U2048::from(75)).unwrap();
+        assert_eq!(ctx.mul_mod(U2048::from(128), U2048::from(23)), U2048::from(19));
+
+        // Product of the two factors of RSA-100 modulo a larger odd modulus.
+        let p = U2048::from_dec_str("37975227936943673922808872755445627854565536638199").unwrap();
+        let q = U2048::from_dec_str("40094690950920881030683735292761468389214899724061").unwrap();
+        let n = U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap();
+        let ctx = Montgomery::new(n + U2048::from(2)).unwrap();
+        assert_eq!(ctx.mul_mod(p, q), n);
+    }
+
+    #[test]
+    fn test_pow() {
+        let ctx = Montgomery::new(U2048::from(13)).unwrap();
+        assert_eq!(ctx.pow(U2048::from(2), &[7]), U2048::from(11));
+        assert_eq!(ctx.pow(U2048::from(7), &[15]), U2048::from(5));
+        assert_eq!(ctx.pow(U2048::from(7), &[0]), U2048::from(1));
+
+        // Fermat's little theorem with a 2^127 - 1 (Mersenne prime) modulus and a multi-limb exponent.
+        let p = (U2048::from(1) << 127) - U2048::from(1);
+        let ctx = Montgomery::new(p).unwrap();
+        let exp = p - U2048::from(1);
+        assert_eq!(ctx.pow(U2048::from(3), &exp.0), U2048::from(1));
+        assert_eq!(ctx.pow(U2048::from(3), &(exp >> 1).0), p - U2048::from(1));
+    }
+
+    #[test]
+    fn test_simultaneous_pow() {
+        // 2^5 * 5^7 mod 13
+        let ctx = Montgomery::new(U2048::from(13)).unwrap();
+        assert_eq!(ctx.simultaneous_pow(&[U2048::from(2), U2048::from(5)], &[&[5], &[7]]), U2048::from(9));
+        assert_eq!(ctx.simultaneous_pow(&[], &[]), U2048::from(1));
+
+        // Exponents of different widths.
+        let p = (U2048::from(1) << 127) - U2048::from(1);
+        let ctx = Montgomery::new(p).unwrap();
+        let exp = p - U2048::from(1);
+        let bases = [U2048::from(3), U2048::from(7), U2048::from(10)];
+        let expected = ctx.mul_mod(ctx.mul_mod(ctx.pow(bases[0], &exp.0), ctx.pow(bases[1], &[12345])), ctx.pow(bases[2], &[0, 1]));
+        assert_eq!(ctx.simultaneous_pow(&bases, &[&exp.0, &[12345], &[0, 1]]), expected);
+    }
+
+    #[test]
+    fn test_pow_full_width() {
+        // A modulus that occupies every limb exercises the carry handling of the reduction.
+        let n = U2048::max_value();
+        let ctx = Montgomery::new(n).unwrap();
+        let base = n - U2048::from(1);
+        assert_eq!(ctx.pow(base, &[2]), U2048::from(1));
+        assert_eq!(ctx.pow(base, &[3]), base);
+        assert_eq!(ctx.mul_mod(U2048::from(1) << 2047, U2048::from(2)), U2048::from(1));
+
+        // A modulus whose top significant limb is full(2^256 - 189) carries into the next limb.
+        let n = U2048::from_dec_str("115792089237316195423570985008687907853269984665640564039457584007913129639747").unwrap();
+        let ctx = Montgomery::new(n).unwrap();
+        let a = n - U2048::from(2);
+        assert_eq!(ctx.mul_mod(a, a), U2048::from(4));
+        assert_eq!(ctx.pow(a, &[2]), U2048::from(4));
+    }
+}
@@ -0,0 +1,218 @@
+/// Multiset Accumulators
+/// An element with multiplicity m is accumulated as elem^m so that the same prime can be added several
+/// times. This allows the accumulator to hold identical fungible coins. The power elem^m is a BigUint so the
+/// multiplicity is not bounded by the width of a U2048.
+
+use codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use crate::{proofs, subroutines, witnesses};
+use super::{BigUint, Error, Group, I2048, U2048};
+
+/// Witness that an element is contained in the accumulator with exactly the stated multiplicity. Consists
+/// of a membership witness for elem^multiplicity and a non-membership witness for elem that is checked
+/// against the membership witness, so that no further copies can be hidden in it.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct MultisetWit<E = U2048> {
+    pub multiplicity: u64,
+    pub witness: E,
+    pub non_mem_wit: (I2048, E),
+}
+
+/// Returns elem^multiplicity.
+pub fn elem_power(elem: U2048, multiplicity: u64) -> BigUint {
+    let mut result = BigUint::one();
+    for _ in 0..multiplicity {
+        result = result * elem;
+    }
+    return result;
+}
+
+/// Returns the number of times that elem divides agg.
+pub fn multiplicity(agg: &BigUint, elem: U2048) -> u64 {
+    if elem <= U2048::from(1) {
+        return 0;
+    }
+    let mut count = 0;
+    let mut agg = agg.clone();
+    while !agg.is_zero() {
+        let (quotient, remainder) = agg.div_rem_u2048(elem);
+        if remainder != U2048::from(0) {
+            break;
+        }
+        agg = quotient;
+        count += 1;
+    }
+    return count;
+}
+
+/// Adds "multiplicity" copies of an element to the accumulator.
+pub fn add<G: Group>(group: &G, state: G::Elem, elem: U2048, multiplicity: u64) -> G::Elem {
+    return group.exp_big(&state, &elem_power(elem, multiplicity));
+}
+
+/// Deletes "multiplicity" copies of an element given a witness for at least that many copies.
+pub fn delete<G: Group>(group: &G, state: G::Elem, elem: U2048, multiplicity: u64, witness: G::Elem) -> Result<G::Elem, Error> {
+    if group.exp_big(&witness, &elem_power(elem, multiplicity)) == state {
+        return Ok(witness);
+    }
+    return Err(Error::InvalidWitness);
+}
+
+/// Batch adds a set of (element, multiplicity) pairs. Returns the state after addition, the product of the
+/// added powers, and a proof of exponentiation.
+pub fn batch_add<G: Group>(group: &G, state: G::Elem, elems: &[(U2048, u64)]) -> (G::Elem, BigUint, G::Elem) {
+    let mut x_agg = BigUint::one();
+    for (elem, multiplicity) in elems {
+        x_agg = x_agg * elem_power(*elem, *multiplicity);
+    }
+    let new_state = group.exp_big(&state, &x_agg);
+    let proof = proofs::poe(group, state, &x_agg, new_state);
+    return (new_state, x_agg, proof);
+}
+
+/// Batch deletes a set of (element, multiplicity, witness) triples where each witness covers at least the
+/// stated number of copies. Returns the state after deletion, the product of the deleted powers, and a proof
+/// of exponentiation. The elements must be distinct; copies of the same element are deleted in one triple.
+pub fn batch_delete<G: Group>(group: &G, state: G::Elem, elems: &[(U2048, u64, G::Elem)]) -> Result<(G::Elem, BigUint, G::Elem), Error> {
+    if elems.is_empty() {
+        return Err(Error::Empty);
+    }
+    let (elem, multiplicity, mut new_state) = elems[0];
+    let mut x_agg = elem_power(elem, multiplicity);
+    for i in 1..elems.len() {
+        let (elem, multiplicity, witness) = elems[i];
+        let power = elem_power(elem, multiplicity);
+        new_state = subroutines::shamir_trick_big(group, new_state, witness, &x_agg, &power)?;
+        x_agg = x_agg * power;
+    }
+    // shamir_trick_big only checks the witnesses against each other so the result is checked against the state.
+    if group.exp_big(&new_state, &x_agg) != state {
+        return Err(Error::InvalidWitness);
+    }
+    let proof = proofs::poe(group, new_state, &x_agg, state);
+    return Ok((new_state, x_agg, proof));
+}
+
+/// Creates a witness for "multiplicity" copies of an element contained in "agg".
+/// NOTE: "old_state" represents the state *before* the elements are added.
+pub fn mem_wit_create<G: Group>(group: &G, old_state: G::Elem, agg: &BigUint, elem: U2048, multiplicity: u64) -> Result<G::Elem, Error> {
+    return witnesses::mem_wit_create(group, old_state, agg, &elem_power(elem, multiplicity));
+}
+
+/// Verifies that the accumulator contains at least "multiplicity" copies of an element.
+pub fn verify_mem_wit<G: Group>(group: &G, state: G::Elem, witness: G::Elem, elem: U2048, multiplicity: u64) -> bool {
+    return group.exp_big(&witness, &elem_power(elem, multiplicity)) == state;
+}
+
+/// Updates a witness for "multiplicity" copies of an element. The deletions must not contain the element
+/// itself(see witnesses::update_mem_wit).
+pub fn update_mem_wit<G: Group>(group: &G, elem: U2048, multiplicity: u64, witness: G::Elem, new_state: G::Elem,
+                                additions: &BigUint, deletions: &BigUint) -> Result<G::Elem, Error> {
+    let witness = group.exp_big(&witness, additions);
+    return subroutines::shamir_trick_big(group, new_state, witness, deletions, &elem_power(elem, multiplicity));
+}
+
+/// Creates a witness for the exact multiplicity of an element in "agg". Fails if the element is not contained.
+/// NOTE: "old_state" represents the state *before* the elements are added.
+pub fn exact_mem_wit_create<G: Group>(group: &G, old_state: G::Elem, agg: &BigUint, elem: U2048) -> Result<MultisetWit<G::Elem>, Error> {
+    let multiplicity = multiplicity(agg, elem);
+    if multiplicity == 0 {
+        return Err(Error::NotDivisible);
+    }
+    let (cofactor, _) = agg.div_rem(&elem_power(elem, multiplicity));
+    let witness = group.exp_big(&old_state, &cofactor);
+    let non_mem_wit = witnesses::non_mem_wit_create(group, old_state, &cofactor, elem)?;
+    return Ok(MultisetWit { multiplicity, witness, non_mem_wit });
+}
+
+/// Verifies that the accumulator contains exactly "witness.multiplicity" copies of an element.
+pub fn verify_exact_mem_wit<G: Group>(group: &G, old_state: G::Elem, state: G::Elem, witness: MultisetWit<G::Elem>, elem: U2048) -> bool {
+    return verify_mem_wit(group, state, witness.witness, elem, witness.multiplicity)
+        && witnesses::verify_non_mem_wit(group, old_state, witness.witness, witness.non_mem_wit, elem);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AccumulatorParams;
+
+    const RSA_100: &str = "1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139";
+
+    #[test]
+    fn test_elem_power() {
+        assert_eq!(elem_power(U2048::from(3), 4), BigUint::from(U2048::from(81)));
+        assert_eq!(elem_power(U2048::from(3), 0), BigUint::one());
+        assert_eq!(elem_power(U2048::from(1) << 1024, 3).bits(), 3073);
+        assert_eq!(multiplicity(&BigUint::from(U2048::from(3 * 3 * 3 * 5)), U2048::from(3)), 3);
+        assert_eq!(multiplicity(&BigUint::from(U2048::from(3 * 3 * 3 * 5)), U2048::from(7)), 0);
+    }
+
+    #[test]
+    fn test_multiset() {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        let old_state = params.generator;
+
+        // Three copies of 3, one copy of 5 and two copies of 7.
+        let (state, agg, proof) = batch_add(&params, old_state, &[(U2048::from(3), 3), (U2048::from(5), 1), (U2048::from(7), 2)]);
+        assert_eq!(agg, BigUint::from(U2048::from(27 * 5 * 49)));
+        assert_eq!(proofs::verify_poe(&params, old_state, &agg, state, proof), true);
+
+        // Witnesses for a subset of the copies.
+        let witness = mem_wit_create(&params, old_state, &agg, U2048::from(3), 2).unwrap();
+        assert_eq!(verify_mem_wit(&params, state, witness, U2048::from(3), 2), true);
+        assert_eq!(verify_mem_wit(&params, state, witness, U2048::from(3), 3), false);
+        assert_eq!(mem_wit_create(&params, old_state, &agg, U2048::from(3), 4), Err(Error::NotDivisible));
+
+        // Exact multiplicities.
+        let exact = exact_mem_wit_create(&params, old_state, &agg, U2048::from(3)).unwrap();
+        assert_eq!(exact.multiplicity, 3);
+        assert_eq!(verify_exact_mem_wit(&params, old_state, state, exact, U2048::from(3)), true);
+        let understated = MultisetWit { multiplicity: 2, witness, ..exact };
+        assert_eq!(verify_exact_mem_wit(&params, old_state, state, understated, U2048::from(3)), false);
+
+        // Delete two copies of 3 and one copy of 7.
+        let witness_7 = mem_wit_create(&params, old_state, &agg, U2048::from(7), 1).unwrap();
+        let (new_state, deleted, proof) = batch_delete(&params, state, &[(U2048::from(3), 2, witness), (U2048::from(7), 1, witness_7)]).unwrap();
+        assert_eq!(deleted, BigUint::from(U2048::from(9 * 7)));
+        assert_eq!(new_state, params.exp(&old_state, U2048::from(3 * 5 * 7)));
+        assert_eq!(proofs::verify_poe(&params, new_state, &deleted, state, proof), true);
+        let witness_3 = mem_wit_create(&params, old_state, &agg, U2048::from(3), 1).unwrap();
+        assert_eq!(batch_delete(&params, state, &[(U2048::from(3), 2, witness), (U2048::from(3), 1, witness_3)]), Err(Error::NotCoprime));
+        assert_eq!(delete(&params, state, U2048::from(3), 3, witness), Err(Error::InvalidWitness));
+
+        // A witness for both copies of 7 follows the deletion of other elements.
+        let witness_7 = mem_wit_create(&params, old_state, &agg, U2048::from(7), 2).unwrap();
+        let witness_5 = mem_wit_create(&params, old_state, &agg, U2048::from(5), 1).unwrap();
+        let state_after = delete(&params, state, U2048::from(5), 1, witness_5).unwrap();
+        let updated = update_mem_wit(&params, U2048::from(7), 2, witness_7, state_after, &BigUint::one(), &BigUint::from(U2048::from(5))).unwrap();
+        assert_eq!(verify_mem_wit(&params, state_after, updated, U2048::from(7), 2), true);
+
+        // Adding copies of an element that is already contained.
+        let state = add(&params, new_state, U2048::from(5), 2);
+        assert_eq!(state, params.exp(&old_state, U2048::from(3 * 125 * 7)));
+    }
+
+    #[test]
+    fn test_wide_multiplicity() {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        let old_state = params.generator;
+
+        // 20 copies of a 128 bit prime do not fit in a U2048.
+        let elem = subroutines::hash_to_prime(&params, b"coin");
+        assert_eq!(elem_power(elem, 20).to_u2048(), Err(Error::Overflow));
+        let (state, agg, proof) = batch_add(&params, old_state, &[(elem, 20), (U2048::from(3), 1)]);
+        assert_eq!(proofs::verify_poe(&params, old_state, &agg, state, proof), true);
+
+        let exact = exact_mem_wit_create(&params, old_state, &agg, elem).unwrap();
+        assert_eq!(exact.multiplicity, 20);
+        assert_eq!(verify_exact_mem_wit(&params, old_state, state, exact, elem), true);
+
+        let witness_3 = mem_wit_create(&params, old_state, &agg, U2048::from(3), 1).unwrap();
+        let (new_state, deleted, _) = batch_delete(&params, state, &[(elem, 20, exact.witness), (U2048::from(3), 1, witness_3)]).unwrap();
+        assert_eq!(new_state, old_state);
+        assert_eq!(deleted, agg);
+        assert_eq!(delete(&params, state, elem, 21, exact.witness), Err(Error::InvalidWitness));
+    }
+}
@@ -0,0 +1,288 @@
+/// Fixed-Base Precomputation
+/// Most exponentiations of a block share the same base: additions raise the current state, witnesses are
+/// created from the state before the block, and minting raises the generator. A FixedBase table stores the
+/// powers base^(d * 2^(w*i)) for every window i and digit d < 2^w, so that raising the base to a power
+/// takes one group operation per nonzero window and no squarings. With 4 bit windows an exponentiation needs
+/// about a sixth of the operations of square-and-multiply and building the table costs about three of them.
+/// RSA groups exponentiate with sliding windows, so there a table pays off after about eight exponentiations.
+/// Tables are opt-in: wrapping a group in Precomputed routes every exponentiation of the fixed base
+/// through the table, so batch_add, root_factor and witnesses::mem_wit_create use it unchanged.
+
+use codec::Encode;
+use rstd::prelude::Vec;
+use super::{BigUint, Group, U2048};
+
+/// Default window width in bits. Larger windows halve the operations per exponentiation at the cost of
+/// doubling the table.
+pub const WINDOW_BITS: usize = 4;
+
+/// Windowed table of powers of a fixed base.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct FixedBase<E> {
+    base: E,
+    window: usize,
+    max_bits: usize,
+    // table[i][d - 1] = base^(d * 2^(window*i)).
+    table: Vec<Vec<E>>,
+    // base^(2^max_bits), the base for the bits that exceed the table.
+    top: E,
+}
+
+impl<E: Copy + Eq> FixedBase<E> {
+    /// Builds a table that covers exponents of up to "max_bits" bits with windows of "window" bits.
+    pub fn new<G: Group<Elem = E>>(group: &G, base: E, max_bits: usize, window: usize) -> Self {
+        assert!(window > 0 && window < 16, "window must be between 1 and 15 bits");
+        let windows = (max_bits + window - 1) / window;
+        let mut table = Vec::with_capacity(windows);
+        let mut power = base;
+        for _ in 0..windows {
+            let mut row = Vec::with_capacity((1 << window) - 1);
+            row.push(power);
+            for d in 1..(1 << window) - 1 {
+                row.push(group.op(&row[d - 1], &power));
+            }
+            // base^(2^(window*(i+1))) = (base^(2^(window*i)))^(2^window).
+            for _ in 0..window {
+                power = group.op(&power, &power);
+            }
+            table.push(row);
+        }
+        return FixedBase { base, window, max_bits: windows * window, table, top: power };
+    }
+
+    /// The fixed base of the table.
+    pub fn base(&self) -> E {
+        return self.base;
+    }
+
+    /// Computes base^exp. Bits beyond the size of the table are handled with square-and-multiply.
+    pub fn exp_big<G: Group<Elem = E>>(&self, group: &G, exp: &BigUint) -> E {
+        let mut result = group.identity();
+        let windows = core::cmp::min(self.table.len(), (exp.bits() + self.window - 1) / self.window);
+        for i in 0..windows {
+            let mut digit = 0;
+            for j in (0..self.window).rev() {
+                digit = (digit << 1) | exp.bit(i * self.window + j) as usize;
+            }
+            if digit != 0 {
+                result = group.op(&result, &self.table[i][digit - 1]);
+            }
+        }
+        if exp.bits() > self.max_bits {
+            result = group.op(&result, &group.exp_big(&self.top, &exp.shr(self.max_bits)));
+        }
+        return result;
+    }
+
+    /// Computes base^exp.
+    pub fn exp<G: Group<Elem = E>>(&self, group: &G, exp: U2048) -> E {
+        return self.exp_big(group, &BigUint::from(exp));
+    }
+}
+
+/// A group whose exponentiations of one fixed base are served from a precomputed table. Behaves exactly
+/// like the wrapped group otherwise; in particular it encodes like the wrapped group, so proofs created
+/// with it verify against the plain group.
+pub struct Precomputed<'a, G: Group> {
+    group: &'a G,
+    table: FixedBase<G::Elem>,
+}
+
+impl<'a, G: Group> Precomputed<'a, G> {
+    /// Precomputes a table for "base" that covers exponents of up to "max_bits" bits(Ex: the bit size of
+    /// the product of the elements added in a block).
+    pub fn new(group: &'a G, base: G::Elem, max_bits: usize) -> Self {
+        return Precomputed { group, table: FixedBase::new(group, base, max_bits, WINDOW_BITS) };
+    }
+
+    /// Wraps a group around an existing table.
+    pub fn from_table(group: &'a G, table: FixedBase<G::Elem>) -> Self {
+        return Precomputed { group, table };
+    }
+
+    pub fn table(&self) -> &FixedBase<G::Elem> {
+        return &self.table;
+    }
+}
+
+impl<'a, G: Group> Encode for Precomputed<'a, G> {
+    fn size_hint(&self) -> usize {
+        return self.group.size_hint();
+    }
+
+    fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+        return self.group.using_encoded(f);
+    }
+}
+
+impl<'a, G: Group> Group for Precomputed<'a, G> {
+    type Elem = G::Elem;
+
+    fn identity(&self) -> G::Elem {
+        return self.group.identity();
+    }
+
+    fn generator(&self) -> G::Elem {
+        return self.group.generator();
+    }
+
+    fn op(&self, a: &G::Elem, b: &G::Elem) -> G::Elem {
+        return self.group.op(a, b);
+    }
+
+    fn exp(&self, base: &G::Elem, exp: U2048) -> G::Elem {
+        if *base == self.table.base {
+            return self.table.exp(self.group, exp);
+        }
+        return self.group.exp(base, exp);
+    }
+
+    fn exp_big(&self, base: &G::Elem, exp: &BigUint) -> G::Elem {
+        if *base == self.table.base {
+            return self.table.exp_big(self.group, exp);
+        }
+        return self.group.exp_big(base, exp);
+    }
+
+    fn simultaneous_exp(&self, bases: &[G::Elem], exps: &[BigUint]) -> G::Elem {
+        return self.group.simultaneous_exp(bases, exps);
+    }
+
+    fn inverse(&self, a: &G::Elem) -> G::Elem {
+        return self.group.inverse(a);
+    }
+
+    fn is_valid(&self, a: &G::Elem) -> bool {
+        return self.group.is_valid(a);
+    }
+
+    fn lambda(&self) -> u32 {
+        return self.group.lambda();
+    }
+
+    fn encode_elem(&self, a: &G::Elem) -> Vec<u8> {
+        return self.group.encode_elem(a);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{batch_add, proofs, subroutines, witnesses, AccumulatorParams};
+    use crate::class_group::ClassGroup;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// RSA group that counts its group operations and exponentiates with square-and-multiply on top of them.
+    struct Counting {
+        params: AccumulatorParams,
+        ops: AtomicUsize,
+    }
+
+    impl Counting {
+        fn take_ops(&self) -> usize {
+            return self.ops.swap(0, Ordering::SeqCst);
+        }
+    }
+
+    impl Encode for Counting {
+        fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+            return self.params.using_encoded(f);
+        }
+    }
+
+    impl Group for Counting {
+        type Elem = U2048;
+
+        fn identity(&self) -> U2048 {
+            return self.params.identity();
+        }
+
+        fn generator(&self) -> U2048 {
+            return self.params.generator();
+        }
+
+        fn op(&self, a: &U2048, b: &U2048) -> U2048 {
+            self.ops.fetch_add(1, Ordering::SeqCst);
+            return self.params.op(a, b);
+        }
+
+        fn exp(&self, base: &U2048, exp: U2048) -> U2048 {
+            return self.exp_big(base, &BigUint::from(exp));
+        }
+
+        fn inverse(&self, a: &U2048) -> U2048 {
+            return self.params.inverse(a);
+        }
+
+        fn is_valid(&self, a: &U2048) -> bool {
+            return self.params.is_valid(a);
+        }
+
+        fn lambda(&self) -> u32 {
+            return self.params.lambda();
+        }
+    }
+
+    #[test]
+    fn test_fixed_base() {
+        let params = AccumulatorParams::default();
+        let base = params.exp(&params.generator, U2048::from(12345));
+        let table = FixedBase::new(&params, base, 300, WINDOW_BITS);
+        let small = BigUint::from(U2048::from(0xdeadbeefu64));
+        let wide = BigUint::from(U2048::max_value()) * U2048::from(1234567);
+        for exp in &[BigUint::zero(), BigUint::one(), small, wide] {
+            assert_eq!(table.exp_big(&params, exp), params.exp_big(&base, exp));
+        }
+        assert_eq!(table.exp(&params, U2048::from(77)), params.exp(&base, U2048::from(77)));
+
+        // Windows of other widths give the same results.
+        let exp = BigUint::from(U2048::from(1) << 299) * U2048::from(3);
+        for window in &[1, 3, 5] {
+            assert_eq!(FixedBase::new(&params, base, 300, *window).exp_big(&params, &exp), params.exp_big(&base, &exp));
+        }
+
+        let group = ClassGroup::from_seed(b"test_fixed_base", 128);
+        let table = FixedBase::new(&group, group.generator(), 200, WINDOW_BITS);
+        let exp = BigUint::from(U2048::from(1) << 250) * U2048::from(99);
+        assert_eq!(table.exp_big(&group, &exp), group.exp_big(&group.generator(), &exp));
+    }
+
+    #[test]
+    fn test_fixed_base_ops() {
+        let group = Counting { params: AccumulatorParams::rsa_2048(), ops: AtomicUsize::new(0) };
+        let base = group.params.exp(&group.params.generator, U2048::from(12345));
+        let elems: Vec<U2048> = (0..16).map(|i| subroutines::hash_to_prime(&group.params, &[i])).collect();
+        let exp = subroutines::prime_product(&elems);
+        let table = FixedBase::new(&group, base, exp.bits(), WINDOW_BITS);
+        let build_ops = group.take_ops();
+
+        let plain = group.exp_big(&base, &exp);
+        let plain_ops = group.take_ops();
+        assert_eq!(table.exp_big(&group, &exp), plain);
+        let table_ops = group.take_ops();
+
+        // One operation per window instead of one squaring per bit plus one multiplication per set bit.
+        assert!(table_ops <= (exp.bits() + WINDOW_BITS - 1) / WINDOW_BITS);
+        assert!(plain_ops >= exp.bits());
+        assert!(6 * table_ops < plain_ops);
+        assert!(build_ops < 4 * plain_ops);
+    }
+
+    #[test]
+    fn test_precomputed() {
+        let params = AccumulatorParams::default();
+        let state = params.exp(&params.generator, U2048::from(31));
+        let elems: Vec<U2048> = (0..6).map(|i| subroutines::hash_to_prime(&params, &[i])).collect();
+        let precomputed = Precomputed::new(&params, state, 6 * crate::LAMBDA as usize);
+        assert_eq!(precomputed.encode(), params.encode());
+
+        // Results and proofs are identical to those of the plain group.
+        let (new_state, agg, proof) = batch_add(&precomputed, state, &elems);
+        assert_eq!((new_state, agg.clone(), proof), batch_add(&params, state, &elems));
+        assert_eq!(proofs::verify_poe(&params, state, &agg, new_state, proof), true);
+
+        let witness = witnesses::mem_wit_create(&precomputed, state, &agg, &BigUint::from(elems[2])).unwrap();
+        assert_eq!(witnesses::verify_mem_wit(&params, new_state, witness, elems[2]), true);
+        assert_eq!(subroutines::root_factor(&precomputed, state, &elems), subroutines::root_factor(&params, state, &elems));
+    }
+}
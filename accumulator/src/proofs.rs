@@ -1,82 +1,204 @@
 /// Succinct Non-interactive Proofs of Exponentiation.
 
-use runtime_io::blake2_256;
-use codec::{Encode};
 use crate::subroutines;
-use super::U2048;
+use crate::transcript::{self, Transcript};
+use super::{BigUint, Group, I2048, U2048};
 
 /// Generates proof of exponentiation that u^x = w (based on Wesolowski). Protocol is only useful
 /// if the verifier can compute the residue r = x mod l faster than computing u^x.
-/// To investigate: Security parameter should be larger than that of accumulator elements.
-pub fn poe(u: U2048, x: U2048, w: U2048) -> U2048 {
-    let l = subroutines::hash_to_prime(&(u, x, w).encode());
-    let q = x / l;
-    return subroutines::mod_exp(u, q, U2048::from_dec_str(super::MODULUS).unwrap());
+pub fn poe<G: Group>(group: &G, u: G::Elem, x: &BigUint, w: G::Elem) -> G::Elem {
+    let l = poe_challenge(group, u, x, w);
+    let (q, _) = x.div_rem_u2048(l);
+    return group.exp_big(&u, &q);
 }
 
 /// Verifies proof of exponentiation.
-pub fn verify_poe(u: U2048, x: U2048, w: U2048, Q: U2048) -> bool {
-    let l = subroutines::hash_to_prime(&(u, x, w).encode());
-    let r = x % l;
-    let lhs = subroutines::mul_mod(subroutines::mod_exp(Q, l, U2048::from_dec_str(super::MODULUS).unwrap()), subroutines::mod_exp(u, r, U2048::from_dec_str(super::MODULUS).unwrap()),
-                                   U2048::from_dec_str(super::MODULUS).unwrap());
+pub fn verify_poe<G: Group>(group: &G, u: G::Elem, x: &BigUint, w: G::Elem, Q: G::Elem) -> bool {
+    let l = poe_challenge(group, u, x, w);
+    let (_, r) = x.div_rem_u2048(l);
+    let lhs = group.op(&group.exp(&Q, l), &group.exp(&u, r));
     return lhs == w;
 }
 
+/// Derives the prime challenge of a proof of exponentiation from a PoE transcript.
+pub fn poe_challenge<G: Group>(group: &G, u: G::Elem, x: &BigUint, w: G::Elem) -> U2048 {
+    let mut transcript = Transcript::new(transcript::POE, group);
+    transcript.append(b"u", &u);
+    transcript.append(b"x", x);
+    transcript.append(b"w", &w);
+    return transcript.challenge_prime(b"l");
+}
+
 /// Generates proof of knowledge of exponentiation that u^x = w. We will assume that the generator
-/// g = 2 is a group element of unknown order.
-/// To investigate: Security parameter should be larger than that of accumulator elements.
-pub fn poke(u: U2048, x: U2048, w: U2048) -> (U2048, U2048, U2048) {
-    let z = subroutines::mod_exp(U2048::from(2), x, U2048::from_dec_str(super::MODULUS).unwrap());
-    let l = subroutines::hash_to_prime(&(u, w, z).encode());
-    let alpha = U2048::from_little_endian(&blake2_256(&(u, w, z, l).encode()));
-    let q = x / l;
-    let r = x % l;
-    let Q = subroutines::mod_exp(subroutines::mul_mod(u, subroutines::mod_exp(U2048::from(2), alpha, U2048::from_dec_str(super::MODULUS).unwrap()),
-                                                      U2048::from_dec_str(super::MODULUS).unwrap()), q, U2048::from_dec_str(super::MODULUS).unwrap());
-    let pi = (z, Q, r);
-    return pi;
+/// of the group is an element of unknown order.
+pub fn poke<G: Group>(group: &G, u: G::Elem, x: U2048, w: G::Elem) -> (G::Elem, G::Elem, U2048) {
+    return poke2(group, u, I2048::from(x), w);
 }
 
 /// Verifies proof of knowledge of exponentiation.
-pub fn verify_poke(u: U2048, w: U2048, z: U2048, Q: U2048, r: U2048) -> bool {
-    let l = subroutines::hash_to_prime(&(u, w, z).encode());
-    let alpha = U2048::from_little_endian(&blake2_256(&(u, w, z, l).encode()));
-    let lhs = subroutines::mul_mod(subroutines::mod_exp(Q, l, U2048::from_dec_str(super::MODULUS).unwrap()),
-                                   subroutines::mod_exp(subroutines::mul_mod(u, subroutines::mod_exp(U2048::from(2), alpha, U2048::from_dec_str(super::MODULUS).unwrap()),
-                                                                             U2048::from_dec_str(super::MODULUS).unwrap()), r, U2048::from_dec_str(super::MODULUS).unwrap()), U2048::from_dec_str(super::MODULUS).unwrap());
-    let rhs = subroutines::mul_mod(w, subroutines::mod_exp(z, alpha, U2048::from_dec_str(super::MODULUS).unwrap()), U2048::from_dec_str(super::MODULUS).unwrap());
+pub fn verify_poke<G: Group>(group: &G, u: G::Elem, w: G::Elem, z: G::Elem, Q: G::Elem, r: U2048) -> bool {
+    return verify_poke2(group, u, w, z, Q, r);
+}
+
+/// Generates NI-PoKE2 that u^x = w for a possibly negative integer x. The proof consists of z = g^x,
+/// Q = (u * g^alpha)^q and r where x = q*l + r with 0 <= r < l.
+pub fn poke2<G: Group>(group: &G, u: G::Elem, x: I2048, w: G::Elem) -> (G::Elem, G::Elem, U2048) {
+    let g = group.generator();
+    let z = group.exp_signed(&g, x);
+    let (l, alpha) = poke2_challenge(group, u, w, z);
+    let q = x.div_floor(I2048::from(l));
+    let r = x.rem_euclid(l);
+    let Q = group.exp_signed(&group.op(&u, &group.exp(&g, alpha)), q);
+    return (z, Q, r);
+}
+
+/// Generates NI-PoKE2 that u^x = w for a non-negative integer x of any width. Verified by verify_poke2.
+pub fn poke2_big<G: Group>(group: &G, u: G::Elem, x: &BigUint, w: G::Elem) -> (G::Elem, G::Elem, U2048) {
+    let g = group.generator();
+    let z = group.exp_big(&g, x);
+    let (l, alpha) = poke2_challenge(group, u, w, z);
+    let (q, r) = x.div_rem_u2048(l);
+    let Q = group.exp_big(&group.op(&u, &group.exp(&g, alpha)), &q);
+    return (z, Q, r);
+}
+
+/// Verifies NI-PoKE2.
+pub fn verify_poke2<G: Group>(group: &G, u: G::Elem, w: G::Elem, z: G::Elem, Q: G::Elem, r: U2048) -> bool {
+    let g = group.generator();
+    let (l, alpha) = poke2_challenge(group, u, w, z);
+    if r >= l {
+        return false;
+    }
+    let lhs = group.op(&group.exp(&Q, l), &group.exp(&group.op(&u, &group.exp(&g, alpha)), r));
+    let rhs = group.op(&w, &group.exp(&z, alpha));
     return lhs == rhs;
 }
 
+/// Derives the prime challenge l and the scalar challenge alpha of NI-PoKE2 from a PoKE2 transcript.
+pub fn poke2_challenge<G: Group>(group: &G, u: G::Elem, w: G::Elem, z: G::Elem) -> (U2048, U2048) {
+    let mut transcript = Transcript::new(transcript::POKE2, group);
+    transcript.append(b"u", &u);
+    transcript.append(b"w", &w);
+    transcript.append(b"z", &z);
+    let l = transcript.challenge_prime(b"l");
+    let alpha = transcript.challenge_scalar(b"alpha");
+    return (l, alpha);
+}
+
+/// Generates NI-PoKCR(proof of knowledge of co-prime roots) for a set of roots alpha_i where alpha_i^x_i = w_i
+/// and the x_i are pairwise coprime. The proof is simply the product of the roots. Can be used to aggregate
+/// the quotients of several PoE/PoKE2 proofs into a single element.
+pub fn pokcr<G: Group>(group: &G, roots: &[G::Elem]) -> G::Elem {
+    let mut proof = group.identity();
+    for root in roots.iter() {
+        proof = group.op(&proof, root);
+    }
+    return proof;
+}
+
+/// Verifies NI-PoKCR by checking that proof^(x_1*...*x_n) = w_1^(x*/x_1) * ... * w_n^(x*/x_n).
+pub fn verify_pokcr<G: Group>(group: &G, values: &[G::Elem], exps: &[U2048], proof: G::Elem) -> bool {
+    if values.len() != exps.len() || values.is_empty() {
+        return false;
+    }
+    let rhs = subroutines::multi_exp(group, values, exps);
+    return group.exp_big(&proof, &subroutines::prime_product(exps)) == rhs;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::AccumulatorParams;
+    use rstd::prelude::Vec;
+
+    const RSA_100: &str = "1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139";
 
     #[test]
     fn test_poe() {
-        let mut proof = poe(U2048::from(2), U2048::from(6), U2048::from(12));
-        assert_eq!(verify_poe(U2048::from(2), U2048::from(6), U2048::from(12), proof), true);
+        let params = AccumulatorParams::default();
+        let x = BigUint::from(U2048::from(6));
+        let mut proof = poe(&params, U2048::from(2), &x, U2048::from(12));
+        assert_eq!(verify_poe(&params, U2048::from(2), &x, U2048::from(12), proof), true);
 
-        proof = poe(U2048::from(121314), U2048::from(14123), U2048::from(6));
-        assert_eq!(verify_poe(U2048::from(121314), U2048::from(14123), U2048::from(6), proof), true);
+        let x = BigUint::from(U2048::from(14123));
+        proof = poe(&params, U2048::from(121314), &x, U2048::from(6));
+        assert_eq!(verify_poe(&params, U2048::from(121314), &x, U2048::from(6), proof), true);
 
         // Fake proof
-        assert_eq!(verify_poe(U2048::from(2), U2048::from(6), U2048::from(12), U2048::from(3)), false);
-        assert_eq!(verify_poe(U2048::from(4), U2048::from(12), U2048::from(7), U2048::from(1)), false);
+        assert_eq!(verify_poe(&params, U2048::from(2), &BigUint::from(U2048::from(6)), U2048::from(12), U2048::from(3)), false);
+        assert_eq!(verify_poe(&params, U2048::from(4), &BigUint::from(U2048::from(12)), U2048::from(7), U2048::from(1)), false);
+    }
+
+    #[test]
+    fn test_poe_wide_exponent() {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        // The product of 40 128-bit primes does not fit in a U2048.
+        let elems: Vec<U2048> = (0..40u8).map(|i| subroutines::hash_to_prime(&params, &[i])).collect();
+        let x = subroutines::prime_product(&elems);
+        assert_eq!(x.bits() > 2048, true);
+
+        let u = U2048::from(3);
+        let w = params.exp_big(&u, &x);
+        let proof = poe(&params, u, &x, w);
+        assert_eq!(verify_poe(&params, u, &x, w, proof), true);
+        assert_eq!(verify_poe(&params, u, &(x.clone() * U2048::from(3)), w, proof), false);
+        assert_eq!(verify_poe(&params, u, &x, params.op(&w, &u), proof), false);
     }
 
     #[test]
     fn test_poke() {
-        let (z, Q, r) = poke(U2048::from(2), U2048::from(6), U2048::from(12));
-        assert_eq!(verify_poke(U2048::from(2), U2048::from(12), z, Q, r), true);
+        let params = AccumulatorParams::default();
+        let (z, Q, r) = poke(&params, U2048::from(2), U2048::from(6), U2048::from(12));
+        assert_eq!(verify_poke(&params, U2048::from(2), U2048::from(12), z, Q, r), true);
 
-        let (z, Q, r) = poke(U2048::from(121314), U2048::from(14123), U2048::from(6));
-        assert_eq!(verify_poke(U2048::from(121314), U2048::from(6), z, Q, r), true);
+        let (z, Q, r) = poke(&params, U2048::from(121314), U2048::from(14123), U2048::from(6));
+        assert_eq!(verify_poke(&params, U2048::from(121314), U2048::from(6), z, Q, r), true);
 
         // Fake proof
-        assert_eq!(verify_poke(U2048::from(121314), U2048::from(7), z, Q, r), false);
-        assert_eq!(verify_poke(U2048::from(2), U2048::from(12), U2048::from(4), U2048::from(1), U2048::from(2)), false);
+        assert_eq!(verify_poke(&params, U2048::from(121314), U2048::from(7), z, Q, r), false);
+        assert_eq!(verify_poke(&params, U2048::from(2), U2048::from(12), U2048::from(4), U2048::from(1), U2048::from(2)), false);
+    }
+
+    #[test]
+    fn test_poke2() {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        let u = U2048::from(7);
+        let x = I2048::from(-123456789);
+        let w = params.exp_signed(&u, x);
+        let (z, Q, r) = poke2(&params, u, x, w);
+        assert_eq!(verify_poke2(&params, u, w, z, Q, r), true);
+
+        // Wrong statement and tampered proofs
+        assert_eq!(verify_poke2(&params, u, params.exp(&u, U2048::from(123456789)), z, Q, r), false);
+        assert_eq!(verify_poke2(&params, u, w, z, Q, r + U2048::from(1)), false);
+        let (l, _) = poke2_challenge(&params, u, w, z);
+        assert_eq!(verify_poke2(&params, u, w, z, Q, r + l), false);
+    }
+
+    #[test]
+    fn test_poke2_big() {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        let u = U2048::from(7);
+        let elems: Vec<U2048> = (0..20u8).map(|i| subroutines::hash_to_prime(&params, &[i])).collect();
+        let x = subroutines::prime_product(&elems);
+        let w = params.exp_big(&u, &x);
+        let (z, Q, r) = poke2_big(&params, u, &x, w);
+        assert_eq!(z, params.exp_big(&params.generator, &x));
+        assert_eq!(verify_poke2(&params, u, w, z, Q, r), true);
+        assert_eq!(verify_poke2(&params, u, params.op(&w, &u), z, Q, r), false);
+    }
+
+    #[test]
+    fn test_pokcr() {
+        let params = AccumulatorParams::new(U2048::from_dec_str(RSA_100).unwrap(), U2048::from(2), crate::LAMBDA).unwrap();
+        let roots = vec![U2048::from(3), U2048::from(5), U2048::from(7)];
+        let exps = vec![U2048::from(11), U2048::from(13), U2048::from(17)];
+        let values: Vec<U2048> = roots.iter().zip(exps.iter()).map(|(root, x)| params.exp(root, *x)).collect();
+
+        let proof = pokcr(&params, &roots);
+        assert_eq!(verify_pokcr(&params, &values, &exps, proof), true);
+        assert_eq!(verify_pokcr(&params, &values, &exps, params.op(&proof, &U2048::from(2))), false);
+        assert_eq!(verify_pokcr(&params, &values[..2], &exps, proof), false);
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,212 @@
+/// Signed BigInts
+/// Sign-magnitude integers built on top of U2048. Used wherever intermediate values can become negative,
+/// such as Bezout coefficients.
+
+use codec::{Encode, Decode, Input};
+use core::cmp::Ordering;
+use core::ops::{Add, Sub, Mul, Div, Rem, Neg};
+use super::U2048;
+
+/// Signed integer with a 2048 bit magnitude. Zero is always represented as non-negative so that
+/// the derived equality is well-defined.
+#[derive(Encode, Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct I2048 {
+    magnitude: U2048,
+    negative: bool,
+}
+
+impl I2048 {
+    /// Creates a signed integer from a magnitude and a sign(true indicates negative).
+    pub fn new(magnitude: U2048, negative: bool) -> Self {
+        I2048 {
+            magnitude,
+            negative: negative && !magnitude.is_zero(),
+        }
+    }
+
+    pub fn zero() -> Self {
+        return I2048::new(U2048::from(0), false);
+    }
+
+    pub fn one() -> Self {
+        return I2048::new(U2048::from(1), false);
+    }
+
+    /// Returns the absolute value as an unsigned integer.
+    pub fn magnitude(&self) -> U2048 {
+        return self.magnitude;
+    }
+
+    pub fn is_negative(&self) -> bool {
+        return self.negative;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.magnitude.is_zero();
+    }
+
+    pub fn abs(&self) -> Self {
+        return I2048::new(self.magnitude, false);
+    }
+
+    /// Returns the unique value r in [0, modulus) such that self = q * modulus + r for some q.
+    pub fn rem_euclid(&self, modulus: U2048) -> U2048 {
+        let r = self.magnitude % modulus;
+        if self.negative && !r.is_zero() {
+            return modulus - r;
+        }
+        return r;
+    }
+
+    /// Floored division: rounds the quotient towards negative infinity.
+    pub fn div_floor(&self, rhs: I2048) -> I2048 {
+        let q = *self / rhs;
+        if !(*self % rhs).is_zero() && (self.negative != rhs.negative) {
+            return q - I2048::one();
+        }
+        return q;
+    }
+}
+
+impl Decode for I2048 {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let magnitude = U2048::decode(input)?;
+        let negative = bool::decode(input)?;
+        return Ok(I2048::new(magnitude, negative));
+    }
+}
+
+impl From<U2048> for I2048 {
+    fn from(value: U2048) -> Self {
+        return I2048::new(value, false);
+    }
+}
+
+impl From<i64> for I2048 {
+    fn from(value: i64) -> Self {
+        let magnitude = U2048::from((value as i128).abs() as u128);
+        return I2048::new(magnitude, value < 0);
+    }
+}
+
+impl Ord for I2048 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for I2048 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Neg for I2048 {
+    type Output = I2048;
+
+    fn neg(self) -> I2048 {
+        return I2048::new(self.magnitude, !self.negative);
+    }
+}
+
+impl Add for I2048 {
+    type Output = I2048;
+
+    fn add(self, rhs: I2048) -> I2048 {
+        if self.negative == rhs.negative {
+            return I2048::new(self.magnitude + rhs.magnitude, self.negative);
+        }
+        // Signs differ so the result takes the sign of the operand with the larger magnitude.
+        if self.magnitude >= rhs.magnitude {
+            return I2048::new(self.magnitude - rhs.magnitude, self.negative);
+        }
+        return I2048::new(rhs.magnitude - self.magnitude, rhs.negative);
+    }
+}
+
+impl Sub for I2048 {
+    type Output = I2048;
+
+    fn sub(self, rhs: I2048) -> I2048 {
+        return self + (-rhs);
+    }
+}
+
+impl Mul for I2048 {
+    type Output = I2048;
+
+    fn mul(self, rhs: I2048) -> I2048 {
+        return I2048::new(self.magnitude * rhs.magnitude, self.negative != rhs.negative);
+    }
+}
+
+/// Truncated division(rounds towards zero) to match the semantics of primitive integers.
+impl Div for I2048 {
+    type Output = I2048;
+
+    fn div(self, rhs: I2048) -> I2048 {
+        return I2048::new(self.magnitude / rhs.magnitude, self.negative != rhs.negative);
+    }
+}
+
+/// Remainder of truncated division. Takes the sign of the dividend.
+impl Rem for I2048 {
+    type Output = I2048;
+
+    fn rem(self, rhs: I2048) -> I2048 {
+        return I2048::new(self.magnitude % rhs.magnitude, self.negative);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(I2048::from(5) + I2048::from(-7), I2048::from(-2));
+        assert_eq!(I2048::from(-5) + I2048::from(7), I2048::from(2));
+        assert_eq!(I2048::from(-5) - I2048::from(7), I2048::from(-12));
+        assert_eq!(I2048::from(5) - I2048::from(5), I2048::zero());
+        assert_eq!(I2048::from(-6) * I2048::from(7), I2048::from(-42));
+        assert_eq!(I2048::from(-6) * I2048::from(-7), I2048::from(42));
+        assert_eq!(I2048::from(-6) * I2048::zero(), I2048::zero());
+        assert_eq!(-I2048::zero(), I2048::zero());
+    }
+
+    #[test]
+    fn test_division() {
+        assert_eq!(I2048::from(-7) / I2048::from(2), I2048::from(-3));
+        assert_eq!(I2048::from(-7) % I2048::from(2), I2048::from(-1));
+        assert_eq!(I2048::from(7) % I2048::from(-2), I2048::from(1));
+        assert_eq!(I2048::from(-7).div_floor(I2048::from(2)), I2048::from(-4));
+        assert_eq!(I2048::from(7).div_floor(I2048::from(-2)), I2048::from(-4));
+        assert_eq!(I2048::from(-8).div_floor(I2048::from(2)), I2048::from(-4));
+        assert_eq!(I2048::from(-7).rem_euclid(U2048::from(13)), U2048::from(6));
+        assert_eq!(I2048::from(-26).rem_euclid(U2048::from(13)), U2048::from(0));
+        assert_eq!(I2048::from(20).rem_euclid(U2048::from(13)), U2048::from(7));
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(I2048::from(-3) < I2048::from(2));
+        assert!(I2048::from(-3) < I2048::from(-2));
+        assert!(I2048::from(3) > I2048::from(2));
+        assert_eq!(I2048::from(-3).abs(), I2048::from(3));
+    }
+
+    #[test]
+    fn test_codec() {
+        let value = I2048::from(-1234);
+        assert_eq!(I2048::decode(&mut &value.encode()[..]).unwrap(), value);
+
+        // Negative zero is normalized when decoded.
+        let encoded = (U2048::from(0), true).encode();
+        assert_eq!(I2048::decode(&mut &encoded[..]).unwrap(), I2048::zero());
+    }
+}
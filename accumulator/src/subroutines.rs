@@ -1,18 +1,57 @@
 /// Integer Subroutines for Accumulator Functions.
 
 use runtime_io::blake2_256;
+use codec::Encode;
 use rstd::prelude::Vec;
 use super::U2048;
-use crate::BezoutPair;
+use crate::{AccumulatorParams, BezoutPair, BigUint, Error, Group, I2048};
+use crate::montgomery::Montgomery;
+
+/// Primes used for trial division before the probabilistic primality tests.
+const SMALL_PRIMES: [u64; 25] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73,
+                                 79, 83, 89, 97];
+
+/// Implements fast modular exponentiation. For odd moduli this uses sliding window exponentiation
+/// in Montgomery form. Even moduli fall back to square-and-multiply.
+/// NOTE: Builds a Montgomery context on every call. Repeated operations should go through the group
+/// operations of AccumulatorParams, which reuse the context of the parameters.
+pub fn mod_exp(base: U2048, exp: U2048, modulus: U2048) -> U2048 {
+    match Montgomery::new(modulus) {
+        Some(ctx) => {
+            return ctx.pow(base, &exp.0);
+        },
+        None => {
+            return mod_exp_generic(base, exp, modulus);
+        },
+    }
+}
 
-/// Implements fast modular exponentiation. Algorithm inspired by https://github.com/pwoolcoc/mod_exp-rs/blob/master/src/lib.rs
-/// NOTE: Possible overflow error occurs when size of result exceeds U2048.
-pub fn mod_exp(mut base: U2048, mut exp: U2048, modulus: U2048) -> U2048 {
-    let mut result: U2048 = U2048::from(1);
+/// Modular exponentiation for exponents of arbitrary width(see mod_exp).
+pub fn mod_exp_big(base: U2048, exp: &BigUint, modulus: U2048) -> U2048 {
+    match Montgomery::new(modulus) {
+        Some(ctx) => {
+            return ctx.pow(base, exp.limbs());
+        },
+        None => {
+            // Process the exponent one limb at a time starting from the most significant limb.
+            let mut result = U2048::from(1) % modulus;
+            for &limb in exp.limbs().iter().rev() {
+                result = mod_exp_generic(result, U2048::from(1) << 64, modulus);
+                result = mul_mod_generic(result, mod_exp_generic(base, U2048::from(limb), modulus), modulus);
+            }
+            return result;
+        },
+    }
+}
+
+/// Square-and-multiply exponentiation for moduli that Montgomery arithmetic does not support.
+/// Algorithm inspired by https://github.com/pwoolcoc/mod_exp-rs/blob/master/src/lib.rs
+fn mod_exp_generic(mut base: U2048, mut exp: U2048, modulus: U2048) -> U2048 {
+    let mut result: U2048 = U2048::from(1) % modulus;
     base = base % modulus;
     while exp > U2048::from(0) {
         if exp % U2048::from(2) == U2048::from(1) {
-            result = mul_mod(result, base, modulus);
+            result = mul_mod_generic(result, base, modulus);
         }
 
         if exp == U2048::from(1) {
@@ -20,14 +59,26 @@ pub fn mod_exp(mut base: U2048, mut exp: U2048, modulus: U2048) -> U2048 {
         }
 
         exp = exp >> U2048::from(1);
-        base = mul_mod(base, base, modulus);
+        base = mul_mod_generic(base, base, modulus);
     }
     return result;
 }
 
-/// Defines the multiplication operation for the group. Idea courtesy of:
+/// Defines the multiplication operation for the group. Uses Montgomery multiplication for odd moduli.
+pub fn mul_mod(a: U2048, b: U2048, modulus: U2048) -> U2048 {
+    match Montgomery::new(modulus) {
+        Some(ctx) => {
+            return ctx.mul_mod(a, b);
+        },
+        None => {
+            return mul_mod_generic(a, b, modulus);
+        },
+    }
+}
+
+/// Double-and-add multiplication that avoids overflow for any modulus. Idea courtesy of:
 /// https://www.geeksforgeeks.org/how-to-avoid-overflow-in-modular-multiplication/
-pub fn mul_mod(mut a: U2048, mut b: U2048, modulus: U2048) -> U2048 {
+fn mul_mod_generic(mut a: U2048, mut b: U2048, modulus: U2048) -> U2048 {
     let mut result = U2048::from(0);
     a = a % modulus;
     while b > U2048::from(0) {
@@ -42,113 +93,82 @@ pub fn mul_mod(mut a: U2048, mut b: U2048, modulus: U2048) -> U2048 {
 }
 
 /// Given the xth root of g and yth root of g, finds the xyth root. If the roots are invalid or
-/// x and y are not coprime, None is returned. Otherwise, the function performs relevant modular
-/// inverse operations on the Bezout coefficients and finds the xyth root.
-pub fn shamir_trick(mut xth_root: U2048, mut yth_root: U2048, x: U2048, y: U2048) -> Option<U2048> {
+/// x and y are not coprime, an error is returned. Otherwise, the function raises the roots to the
+/// (possibly negative) Bezout coefficients and finds the xyth root. "x" may be the product of many
+/// elements(Ex: the elements deleted so far in a batch).
+pub fn shamir_trick<G: Group>(group: &G, xth_root: G::Elem, yth_root: G::Elem, x: &BigUint, y: U2048) -> Result<G::Elem, Error> {
     // Check if the inputs are valid.
-    if mod_exp(xth_root, x, U2048::from_dec_str(super::MODULUS).unwrap())
-        != mod_exp(yth_root, y, U2048::from_dec_str(super::MODULUS).unwrap()) {
-        return None;
+    if group.exp_big(&xth_root, x) != group.exp(&yth_root, y) {
+        return Err(Error::InvalidWitness);
     }
 
-    match bezout(x, y) {
-        None => {
-            return None;
-        },
-        Some(coefficients) => {
-            // Receive coefficient
-            let pair = coefficients;
+    // Reduce x so that the Bezout coefficients fit in an I2048. If x = q*y + r and a*r + b*y = 1, then
+    // a*x + (b - a*q)*y = 1.
+    let (q, r) = x.div_rem_u2048(y);
+    let pair = bezout(r, y)?;
 
-            // Calculate relevant modular inverses to allow for exponentiation later on.
-            if pair.sign_b {
-                xth_root = mod_inverse(xth_root);
-            }
-
-            if pair.sign_a {
-                yth_root = mod_inverse(yth_root);
-            }
+    // (g^(1/x))^(b - a*q) * (g^(1/y))^a = g^((a*x + (b - a*q)*y)/xy) = g^(1/xy)
+    let xth_root_b = group.op(&group.exp_signed(&xth_root, pair.coefficient_b),
+                              &group.exp_signed(&group.exp_big(&xth_root, &q), -pair.coefficient_a));
+    let combined_root = group.op(&xth_root_b, &group.exp_signed(&yth_root, pair.coefficient_a));
+    return Ok(combined_root);
+}
 
-            let combined_root: U2048 = (mod_exp(xth_root, U2048::from(pair.coefficient_b), U2048::from_dec_str(super::MODULUS).unwrap())
-                * mod_exp(yth_root, U2048::from(pair.coefficient_a), U2048::from_dec_str(super::MODULUS).unwrap())) % U2048::from_dec_str(super::MODULUS).unwrap();
-            return Some(combined_root);
-        },
+/// Same as shamir_trick for a "y" of any width(Ex: a power of an element in a multiset). Uses the non-negative
+/// Bezout coefficients a = x^-1 mod y and k = (a*x - 1)/y instead of I2048 coefficients.
+pub fn shamir_trick_big<G: Group>(group: &G, xth_root: G::Elem, yth_root: G::Elem, x: &BigUint, y: &BigUint) -> Result<G::Elem, Error> {
+    // Check if the inputs are valid.
+    if group.exp_big(&xth_root, x) != group.exp_big(&yth_root, y) {
+        return Err(Error::InvalidWitness);
     }
+    if *y == BigUint::one() {
+        return Ok(xth_root);
+    }
+
+    // (g^(1/y))^a * (g^(1/x))^(-k) = g^((a*x - k*y)/xy) = g^(1/xy)
+    let a = x.mod_inverse(y)?;
+    let (k, _) = (x.clone() * a.clone()).div_rem(y);
+    let combined_root = group.op(&group.exp_big(&yth_root, &a), &group.inverse(&group.exp_big(&xth_root, &k)));
+    return Ok(combined_root);
 }
 
 /// Computes the modular multiplicative inverse.
 /// NOTE: Does not check if gcd != 1(none exists if so).
-pub fn mod_inverse(elem: U2048) -> U2048 {
-    let (_, pair) = extended_gcd(elem, U2048::from_dec_str(super::MODULUS).unwrap());
-
-    // Accommodate for negative x coefficient
-    if pair.sign_a {
-        // Since we're assuming that the U2048::from(super::MODULUS) will always be larger than than coefficient in
-        // absolute value, we simply subtract x from the U2048::from(super::MODULUS) to get a positive value mod N.
-        let pos_a = U2048::from_dec_str(super::MODULUS).unwrap() - pair.coefficient_a;
-        return pos_a % U2048::from_dec_str(super::MODULUS).unwrap();
-    }
-    return U2048::from(pair.coefficient_a) % U2048::from_dec_str(super::MODULUS).unwrap();
+pub fn mod_inverse(params: &AccumulatorParams, elem: U2048) -> U2048 {
+    let (_, pair) = extended_gcd(elem, params.modulus);
+    return pair.coefficient_a.rem_euclid(params.modulus);
 }
 
 /// Returns Bezout coefficients. Acts as a wrapper for extended_gcd.
-pub fn bezout(a: U2048, b: U2048) -> Option<BezoutPair> {
+pub fn bezout(a: U2048, b: U2048) -> Result<BezoutPair, Error> {
     let (gcd, pair) = extended_gcd(a, b);
     // Check if a and b are coprime
     if gcd != U2048::from(1) {
-        return None;
+        return Err(Error::NotCoprime);
     }
     else {
-        return Some(pair);
+        return Ok(pair);
     }
 }
 
 /// Implements the Extended Euclidean Algorithm (https://en.wikipedia.org/wiki/Extended_Euclidean_algorithm).
-/// IMPORTANT NOTE: Instead of representing the coefficients as signed integers, I have represented
-/// them as (|a|, sign of a) and (|b|, sign of b). This is because the current project lacks
-/// support for signed BigInts.
+/// Returns the gcd of a and b along with the Bezout coefficients.
 pub fn extended_gcd(a: U2048, b: U2048) -> (U2048, BezoutPair) {
-    let (mut s, mut old_s): (U2048, U2048) = (U2048::from(0), U2048::from(1));
-    let (mut t, mut old_t): (U2048, U2048) = (U2048::from(1), U2048::from(0));
+    let (mut s, mut old_s) = (I2048::zero(), I2048::one());
+    let (mut t, mut old_t) = (I2048::one(), I2048::zero());
     let (mut r, mut old_r): (U2048, U2048) = (b, a);
 
-    let (mut prev_sign_s, mut prev_sign_t): (bool, bool) = (false, false);
-    let (mut sign_s, mut sign_t): (bool, bool) = (false, false);
-
     while r != U2048::from(0) {
         let quotient = old_r/r;
-        let new_r = old_r - U2048::from(quotient) * r;
+        let new_r = old_r - quotient * r;
         old_r = r;
         r = new_r;
 
-        // Hacky workaround to track the coefficient "a" as (|a|, sign of a)
-        let mut new_s = quotient * s;
-        if prev_sign_s == sign_s && new_s > old_s {
-            new_s = new_s - old_s;
-            if !sign_s { sign_s = true; }
-            else { sign_s = false; }
-        }
-        else if prev_sign_s != sign_s {
-            new_s = old_s + new_s;
-            prev_sign_s = sign_s;
-            sign_s = !sign_s;
-        }
-        else { new_s = old_s - new_s; }
+        let new_s = old_s - I2048::from(quotient) * s;
         old_s = s;
         s = new_s;
 
-        // Hacky workaround to track the coefficient "b" as (|b|, sign of b)
-        let mut new_t = quotient * t;
-        if prev_sign_t == sign_t && new_t > old_t {
-            new_t = new_t - old_t;
-            if !sign_t { sign_t = true; }
-            else { sign_t = false; }
-        }
-        else if prev_sign_t != sign_t {
-            new_t = old_t + new_t;
-            prev_sign_t = sign_t;
-            sign_t = !sign_t;
-        }
-        else { new_t = old_t - new_t; }
+        let new_t = old_t - I2048::from(quotient) * t;
         old_t = t;
         t = new_t;
     }
@@ -156,64 +176,272 @@ pub fn extended_gcd(a: U2048, b: U2048) -> (U2048, BezoutPair) {
     let pair = BezoutPair {
         coefficient_a: old_s,
         coefficient_b: old_t,
-        sign_a: prev_sign_s,
-        sign_b: prev_sign_t,
     };
 
     return (old_r, pair);
 }
 
-/// Continuously hashes the input until the result is prime. Assumes input values are transcoded in
-/// little endian(uses parity-scale-codec).
-/// Consideration: Currently unclear about the impact of Lambda on the security of the scheme.
-pub fn hash_to_prime(elem: &[u8]) -> U2048 {
-    let mut hash = blake2_256(elem);
-    let mut result = U2048::from_little_endian(&hash) % U2048::from(super::LAMBDA);
+/// Hashes the input to a prime of exactly "lambda" bits(see Group::lambda). Candidates are derived from
+/// the input and an incrementing nonce until one of them passes the Baillie-PSW primality test.
+/// Assumes input values are transcoded in little endian(uses parity-scale-codec).
+pub fn hash_to_prime<G: Group>(group: &G, elem: &[u8]) -> U2048 {
+    let (prime, _) = hash_to_prime_with_nonce(group, elem);
+    return prime;
+}
+
+/// Same as hash_to_prime but also returns the nonce at which the hash produced a prime. The nonce
+/// can be given to a verifier so that it only has to run a single primality test(see page 24 of
+/// https://eprint.iacr.org/2018/1188.pdf).
+pub fn hash_to_prime_with_nonce<G: Group>(group: &G, elem: &[u8]) -> (U2048, u64) {
+    return hash_to_prime_bits(elem, group.lambda() as usize);
+}
 
-    // While the resulting hash is not a prime, keep trying
-    while !miller_rabin(result) {
-        hash = blake2_256(&hash);
-        result = U2048::from_little_endian(&hash) % U2048::from(super::LAMBDA);
+/// Hashes the input to a prime of exactly "bits" bits and returns the nonce at which the hash produced
+/// the prime. Used directly for Fiat-Shamir challenges which are larger than element primes(see transcript).
+pub fn hash_to_prime_bits(elem: &[u8], bits: usize) -> (U2048, u64) {
+    let mut nonce: u64 = 0;
+    loop {
+        let candidate = prime_candidate(elem, nonce, bits);
+        if is_prime(candidate) {
+            return (candidate, nonce);
+        }
+        nonce += 1;
     }
+}
 
-    return result;
+/// Checks that the input hashes to a prime at the given nonce and returns the prime if so.
+/// NOTE: Does not check that the nonce is the smallest one that produces a prime.
+pub fn verify_prime_nonce<G: Group>(group: &G, elem: &[u8], nonce: u64) -> Result<U2048, Error> {
+    let candidate = prime_candidate(elem, nonce, group.lambda() as usize);
+    if !is_prime(candidate) {
+        return Err(Error::NotPrime);
+    }
+    return Ok(candidate);
+}
+
+/// Derives an odd candidate of exactly "bits" bits from the input and a nonce.
+fn prime_candidate(elem: &[u8], nonce: u64, bits: usize) -> U2048 {
+    return hash_to_bits(elem, nonce, bits) | U2048::from(1);
+}
+
+/// Expands a seed into an integer of exactly "bits" bits(the top bit is always set). "bits" must be between 1
+/// and 2048.
+pub fn hash_to_bits(seed: &[u8], counter: u64, bits: usize) -> U2048 {
+    let mut bytes = [0u8; 256];
+    let mut block: u64 = 0;
+    let mut filled = 0;
+    while filled < (bits + 7) / 8 {
+        let hash = blake2_256(&(seed, counter, block).encode());
+        let len = core::cmp::min(32, 256 - filled);
+        bytes[filled..filled + len].copy_from_slice(&hash[..len]);
+        filled += len;
+        block += 1;
+    }
+
+    let mut result = U2048::from_little_endian(&bytes);
+    if bits < 2048 {
+        result = result % (U2048::from(1) << bits);
+    }
+    return result | (U2048::from(1) << (bits - 1));
+}
+
+/// Baillie-PSW primality test: trial division by small primes followed by a strong probable prime
+/// test to base 2 and a strong Lucas probable prime test. No composite is known to pass both tests.
+/// See https://en.wikipedia.org/wiki/Baillie–PSW_primality_test.
+pub fn is_prime(n: U2048) -> bool {
+    if n < U2048::from(2) {
+        return false;
+    }
+    for &p in SMALL_PRIMES.iter() {
+        if n == U2048::from(p) {
+            return true;
+        }
+        if n % U2048::from(p) == U2048::from(0) {
+            return false;
+        }
+    }
+    return strong_probable_prime(n, U2048::from(2)) && strong_lucas_probable_prime(n);
 }
 
-/// Implements a deterministic variant of the Miller-Rabin primality test for u64/u32 integers based
-/// on the algorithm from the following link: https://en.wikipedia.org/wiki/Miller–Rabin_primality_test
-/// Complexity of the algorithm is O((log n)^4) in soft-O notation.
+/// Implements a deterministic variant of the Miller-Rabin primality test based on the algorithm from
+/// the following link: https://en.wikipedia.org/wiki/Miller–Rabin_primality_test
+/// Uses the first 12 primes as bases, which is deterministic for n < 3.18 * 10^23. Use "is_prime" for
+/// larger inputs.
 pub fn miller_rabin(n: U2048) -> bool {
+    // See https://oeis.org/A014233
+    let bases = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n.low_u64() & 1 == 0 {
+        return n == U2048::from(2);
+    }
+    for &a in bases.iter() {
+        // Annoying edge case to make sure a is within [2, n-2] for small n
+        if n-U2048::from(2) < U2048::from(a) { break; }
+
+        if !strong_probable_prime(n, U2048::from(a)) {
+            return false;
+        }
+    }
+    return true;
+}
+
+/// Single round of the Miller-Rabin test. Checks whether an odd n > 2 is a strong probable prime to the
+/// given base.
+fn strong_probable_prime(n: U2048, base: U2048) -> bool {
     // Find r and d such that 2^r * d + 1 = n
     let r = (n-U2048::from(1)).trailing_zeros();
     let d = (n-U2048::from(1)) >> U2048::from(r);
 
-    // See https://stackoverflow.com/questions/7594307/simple-deterministic-primality-testing-for-small-numbers
-    //let bases = [2,3,5,7,11,13,17]; // Deterministic for 64 bit integers
-    let bases = [2, 7, 61];  // Deterministic for 32 bit integers
+    let ctx = Montgomery::new(n).unwrap();
+    let mut x = ctx.pow(base, &d.0);
+    if x == U2048::from(1) || x == (n-U2048::from(1)) {
+        return true;
+    }
+    for _ in 1..r {
+        x = ctx.mul_mod(x, x);
+        if x == (n-U2048::from(1)) {
+            return true;
+        }
+    }
+    return false;
+}
 
-    'outer: for &a in bases.iter() {
-        // Annoying edge case to make sure a is within [2, n-2] for small n
-        if n-U2048::from(2) < U2048::from(a) { break; }
+/// Strong Lucas probable prime test for an odd n > 2 that is not divisible by any small prime.
+/// Parameters are chosen by Selfridge's method: D is the first of 5, -7, 9, -11, ... with Jacobi
+/// symbol (D/n) = -1, P = 1 and Q = (1 - D)/4. All residues are kept in Montgomery form.
+fn strong_lucas_probable_prime(n: U2048) -> bool {
+    let mut d_abs: u64 = 5;
+    let mut negative = false;
+    loop {
+        let d = I2048::new(U2048::from(d_abs), negative);
+        match jacobi(d.rem_euclid(n), n) {
+            -1 => {
+                break;
+            },
+            0 => {
+                if U2048::from(d_abs) != n {
+                    return false;
+                }
+            },
+            _ => {},
+        }
+        // A suitable D never exists for perfect squares.
+        if d_abs == 21 && is_square(n) {
+            return false;
+        }
+        d_abs += 2;
+        negative = !negative;
+    }
 
-        let mut x = mod_exp(U2048::from(a), d, n);
+    let d = I2048::new(U2048::from(d_abs), negative);
+    let q = (I2048::one() - d) / I2048::from(4);
+
+    let ctx = Montgomery::new(n).unwrap();
+    let d_m = ctx.to_montgomery(d.rem_euclid(n));
+    let q_m = ctx.to_montgomery(q.rem_euclid(n));
+
+    // Find s and k such that 2^s * k = n + 1 where k is odd
+    let s = (n + U2048::from(1)).trailing_zeros();
+    let k = (n + U2048::from(1)) >> U2048::from(s);
+
+    // Compute U_k, V_k and Q^k with the binary method starting from U_1 = 1, V_1 = P = 1.
+    let mut u = ctx.to_montgomery(U2048::from(1));
+    let mut v = u;
+    let mut q_k = q_m;
+    for i in (0..(k.bits() - 1)).rev() {
+        // U_2j = U_j * V_j, V_2j = V_j^2 - 2Q^j
+        u = ctx.mul(u, v);
+        v = sub_mod(ctx.mul(v, v), add_mod(q_k, q_k, n), n);
+        q_k = ctx.mul(q_k, q_k);
+        if k.bit(i) {
+            // U_(j+1) = (P*U_j + V_j)/2, V_(j+1) = (D*U_j + P*V_j)/2
+            let u_next = half_mod(add_mod(u, v, n), n);
+            v = half_mod(add_mod(ctx.mul(d_m, u), v, n), n);
+            u = u_next;
+            q_k = ctx.mul(q_k, q_m);
+        }
+    }
 
-        if x == U2048::from(1) || x == (n-U2048::from(1)) {
-            continue;
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    // Check V_(k * 2^r) for 0 < r < s
+    for _ in 1..s {
+        v = sub_mod(ctx.mul(v, v), add_mod(q_k, q_k, n), n);
+        if v.is_zero() {
+            return true;
         }
-        for _ in 1..r {
-            x = mod_exp(x, U2048::from(2), n);
-            if x == (n-U2048::from(1)) {
-                continue 'outer;
+        q_k = ctx.mul(q_k, q_k);
+    }
+    return false;
+}
+
+/// Computes the Jacobi symbol (a/n) for an odd n > 0.
+fn jacobi(mut a: U2048, mut n: U2048) -> i32 {
+    let mut result = 1;
+    a = a % n;
+    while !a.is_zero() {
+        while a.low_u64() & 1 == 0 {
+            a = a >> 1;
+            let r = n.low_u64() & 7;
+            if r == 3 || r == 5 {
+                result = -result;
             }
         }
-        return false;
+        core::mem::swap(&mut a, &mut n);
+        if a.low_u64() & 3 == 3 && n.low_u64() & 3 == 3 {
+            result = -result;
+        }
+        a = a % n;
     }
-    return true;
+    if n == U2048::from(1) {
+        return result;
+    }
+    return 0;
+}
+
+/// Checks whether n is a perfect square using Newton's method for the integer square root.
+fn is_square(n: U2048) -> bool {
+    let mut x = U2048::from(1) << ((n.bits() + 1) / 2);
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            break;
+        }
+        x = y;
+    }
+    return x * x == n;
+}
+
+/// Computes a + b mod n for a, b < n without overflowing.
+fn add_mod(a: U2048, b: U2048, n: U2048) -> U2048 {
+    let (sum, overflow) = a.overflowing_add(b);
+    if overflow || sum >= n {
+        return sum.overflowing_sub(n).0;
+    }
+    return sum;
+}
+
+/// Computes a - b mod n for a, b < n.
+fn sub_mod(a: U2048, b: U2048, n: U2048) -> U2048 {
+    if a >= b {
+        return a - b;
+    }
+    return n - (b - a);
+}
+
+/// Computes a/2 mod n for a < n and odd n.
+fn half_mod(a: U2048, n: U2048) -> U2048 {
+    if a.low_u64() & 1 == 0 {
+        return a >> 1;
+    }
+    // (a + n)/2 without overflowing since both a and n are odd.
+    return (a >> 1) + (n >> 1) + U2048::from(1);
 }
 
 /// Given an element g and a set of elements x, computes the xith root of g^x for each element
-/// in the set. Runs in O(n log(n)).
-pub fn root_factor(g: U2048, elems: &[U2048]) -> Vec<U2048> {
+/// in the set. Runs in O(n log(n)). Each half is raised to the product of its elements in one
+/// exponentiation of g, so a fixed-base group for g(see precompute) serves the top level from its table.
+pub fn root_factor<G: Group>(group: &G, g: G::Elem, elems: &[U2048]) -> Vec<G::Elem> {
     if elems.len() == 1 {
         let mut ret = Vec::new();
         ret.push(g);
@@ -222,29 +450,93 @@ pub fn root_factor(g: U2048, elems: &[U2048]) -> Vec<U2048> {
 
     let n_prime = elems.len()/2;
 
-    let mut g_left = g;
-    for i in 0..n_prime {
-        g_left = mod_exp(g_left, elems[i], U2048::from_dec_str(super::MODULUS).unwrap());
-    }
+    let (g_left, g_right) = join(|| group.exp_big(&g, &prime_product(&elems[..n_prime])),
+                                 || group.exp_big(&g, &prime_product(&elems[n_prime..])));
+
+    let (mut left, mut right) = join(|| root_factor(group, g_right, &elems[0..n_prime]),
+                                     || root_factor(group, g_left, &elems[n_prime..]));
+    left.append(&mut right);
+    return left;
+}
+
+/// Given a base g, an exponent e and a set of elements x_i, computes (g^(e / x_i), e mod x_i) for each
+/// element where "/" is integer division. Shares work across elements by descending a product tree like
+/// RootFactor: if e = q*x + r for the product x = x_l * x_r of both halves, then e / x_l = q*x_r + r / x_l.
+/// NOTE: Expects at least one element and no zero elements.
+pub fn quotient_powers<G: Group>(group: &G, g: G::Elem, e: &BigUint, elems: &[U2048]) -> Vec<(G::Elem, U2048)> {
+    let (q, r) = e.div_rem(&prime_product(elems));
+    return descend_quotient_powers(group, g, group.exp_big(&g, &q), r, elems);
+}
 
-    let mut g_right = g;
-    for i in n_prime..elems.len() {
-        g_right = mod_exp(g_right, elems[i], U2048::from_dec_str(super::MODULUS).unwrap());
+/// Descends the product tree of quotient_powers. "g_q" is g^(e / x) and "r" is e mod x where x is the
+/// product of the elements.
+fn descend_quotient_powers<G: Group>(group: &G, g: G::Elem, g_q: G::Elem, r: BigUint, elems: &[U2048]) -> Vec<(G::Elem, U2048)> {
+    if elems.len() == 1 {
+        let mut ret = Vec::new();
+        ret.push((g_q, r.to_u2048().unwrap()));
+        return ret;
     }
 
-    let mut left = root_factor(g_right, &elems[0..n_prime]);
-    let mut right = root_factor(g_left, &elems[n_prime..]);
+    let n_prime = elems.len()/2;
+    let p_left = prime_product(&elems[0..n_prime]);
+    let p_right = prime_product(&elems[n_prime..]);
+
+    let (q_left, r_left) = r.div_rem(&p_left);
+    let g_left = group.op(&group.exp_big(&g_q, &p_right), &group.exp_big(&g, &q_left));
+    let (q_right, r_right) = r.div_rem(&p_right);
+    let g_right = group.op(&group.exp_big(&g_q, &p_left), &group.exp_big(&g, &q_right));
+
+    let (mut left, mut right) = join(|| descend_quotient_powers(group, g, g_left, r_left, &elems[0..n_prime]),
+                                     || descend_quotient_powers(group, g, g_right, r_right, &elems[n_prime..]));
     left.append(&mut right);
     return left;
 }
 
-/// Short helper function that calculates the product of elements in the vector.
-pub fn prime_product(elems: &[U2048]) -> U2048 {
-    let mut result: U2048 = U2048::from(1);
-    for &elem in elems.iter() {
-        result *= elem;
+/// Given elements w_i and exponents x_i, computes w_1^(x*/x_1) * ... * w_n^(x*/x_n) where x* is the product
+/// of the exponents. Runs in O(n log(n)) by splitting the inputs in half(MultiExp from the paper).
+/// NOTE: Expects at least one value.
+pub fn multi_exp<G: Group>(group: &G, values: &[G::Elem], exps: &[U2048]) -> G::Elem {
+    if values.len() == 1 {
+        return values[0];
     }
-    return result;
+
+    let mid = values.len()/2;
+    let (left, right) = join(|| multi_exp(group, &values[..mid], &exps[..mid]),
+                             || multi_exp(group, &values[mid..], &exps[mid..]));
+    return group.op(&group.exp_big(&left, &prime_product(&exps[mid..])), &group.exp_big(&right, &prime_product(&exps[..mid])));
+}
+
+/// Short helper function that calculates the product of elements in the vector. The halves are
+/// multiplied separately so that the operands of each multiplication have similar sizes.
+pub fn prime_product(elems: &[U2048]) -> BigUint {
+    match elems.len() {
+        0 => {
+            return BigUint::one();
+        },
+        1 => {
+            return BigUint::from(elems[0]);
+        },
+        _ => {
+            let mid = elems.len()/2;
+            let (left, right) = join(|| prime_product(&elems[..mid]), || prime_product(&elems[mid..]));
+            return left * right;
+        },
+    }
+}
+
+/// Evaluates two independent halves of a divide and conquer subroutine. Under the std feature the halves
+/// run in parallel on the rayon thread pool. Results are identical either way.
+#[cfg(feature = "std")]
+fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+    where A: FnOnce() -> RA + Send, B: FnOnce() -> RB + Send, RA: Send, RB: Send {
+    return rayon::join(a, b);
+}
+
+/// Sequential version of join for no_std builds.
+#[cfg(not(feature = "std"))]
+fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+    where A: FnOnce() -> RA + Send, B: FnOnce() -> RB + Send, RA: Send, RB: Send {
+    return (a(), b());
 }
 
 #[cfg(test)]
@@ -258,41 +550,71 @@ mod tests {
                    U2048::from(12));
         assert_eq!(mul_mod(U2048::from(128), U2048::from(23), U2048::from(75)),
                    U2048::from(19));
+        assert_eq!(mul_mod(U2048::from(128), U2048::from(23), U2048::from(74)),
+                   U2048::from(58));
     }
 
     #[test]
     fn test_mod_exp() {
         assert_eq!(mod_exp(U2048::from(2), U2048::from(7), U2048::from_dec_str(MODULUS).unwrap()), U2048::from(11));
         assert_eq!(mod_exp(U2048::from(7), U2048::from(15), U2048::from_dec_str(MODULUS).unwrap()), U2048::from(5));
+        assert_eq!(mod_exp(U2048::from(7), U2048::from(15), U2048::from(10)), U2048::from(3));
+        assert_eq!(mod_exp(U2048::from(7), U2048::from(0), U2048::from(1)), U2048::from(0));
+    }
+
+    #[test]
+    fn test_mod_exp_big() {
+        let exp = BigUint::from((U2048::from(5) << 64) + U2048::from(7));
+        assert_eq!(mod_exp_big(U2048::from(3), &exp, U2048::from(100)), U2048::from(87));
+
+        // Exponent wider than a U2048.
+        let half = BigUint::from(U2048::from(1) << 1050);
+        assert_eq!(mod_exp_big(U2048::from(3), &(half.clone() * half), U2048::from(1000003)), U2048::from(4890));
+        assert_eq!(mod_exp_big(U2048::from(3), &BigUint::zero(), U2048::from(1000003)), U2048::from(1));
     }
 
     #[test]
     fn test_extended_gcd() {
         assert_eq!(extended_gcd(U2048::from(180), U2048::from(150)), (U2048::from(30),
-                   BezoutPair {coefficient_a: U2048::from(1), coefficient_b: U2048::from(1), sign_a: false, sign_b: true}));
+                   BezoutPair {coefficient_a: I2048::from(1), coefficient_b: I2048::from(-1)}));
         assert_eq!(extended_gcd(U2048::from(13), U2048::from(17)), (U2048::from(1),
-                   BezoutPair {coefficient_a: U2048::from(4), coefficient_b: U2048::from(3), sign_a: false, sign_b: true}));
+                   BezoutPair {coefficient_a: I2048::from(4), coefficient_b: I2048::from(-3)}));
     }
 
     #[test]
     fn test_bezout() {
-        assert_eq!(bezout(U2048::from(4), U2048::from(10)), None);
+        assert_eq!(bezout(U2048::from(4), U2048::from(10)), Err(Error::NotCoprime));
         assert_eq!(bezout(U2048::from(3434), U2048::from(2423)),
-                   Some (BezoutPair {coefficient_a: U2048::from(997), coefficient_b: U2048::from(1413), sign_a: true, sign_b: false}));
+                   Ok (BezoutPair {coefficient_a: I2048::from(-997), coefficient_b: I2048::from(1413)}));
     }
 
     #[test]
     fn test_shamir_trick() {
-        assert_eq!(shamir_trick(U2048::from(11), U2048::from(6), U2048::from(7), U2048::from(5)), Some(U2048::from(7)));
-        assert_eq!(shamir_trick(U2048::from(11), U2048::from(7), U2048::from(7), U2048::from(11),), Some(U2048::from(6)));
-        assert_eq!(shamir_trick(U2048::from(6), U2048::from(7), U2048::from(5), U2048::from(11)), Some(U2048::from(11)));
-        assert_eq!(shamir_trick(U2048::from(12), U2048::from(7), U2048::from(7), U2048::from(11)), None);
+        let params = AccumulatorParams::default();
+        assert_eq!(shamir_trick(&params, U2048::from(11), U2048::from(6), &BigUint::from(U2048::from(7)), U2048::from(5)), Ok(U2048::from(7)));
+        assert_eq!(shamir_trick(&params, U2048::from(11), U2048::from(7), &BigUint::from(U2048::from(7)), U2048::from(11)), Ok(U2048::from(6)));
+        assert_eq!(shamir_trick(&params, U2048::from(6), U2048::from(7), &BigUint::from(U2048::from(5)), U2048::from(11)), Ok(U2048::from(11)));
+        assert_eq!(shamir_trick(&params, U2048::from(12), U2048::from(7), &BigUint::from(U2048::from(7)), U2048::from(11)), Err(Error::InvalidWitness));
+        assert_eq!(shamir_trick(&params, U2048::from(6), U2048::from(6), &BigUint::from(U2048::from(5)), U2048::from(5)), Err(Error::NotCoprime));
+    }
+
+    #[test]
+    fn test_shamir_trick_big() {
+        let params = AccumulatorParams::default();
+        let big = |n: u64| BigUint::from(U2048::from(n));
+        assert_eq!(shamir_trick_big(&params, U2048::from(11), U2048::from(6), &big(7), &big(5)), Ok(U2048::from(7)));
+        assert_eq!(shamir_trick_big(&params, U2048::from(6), U2048::from(7), &big(5), &big(11)), Ok(U2048::from(11)));
+        assert_eq!(shamir_trick_big(&params, U2048::from(6), U2048::from(2), &big(5), &big(1)), Ok(U2048::from(6)));
+        assert_eq!(shamir_trick_big(&params, U2048::from(12), U2048::from(7), &big(7), &big(11)), Err(Error::InvalidWitness));
+        assert_eq!(shamir_trick_big(&params, U2048::from(6), U2048::from(6), &big(5), &big(5)), Err(Error::NotCoprime));
     }
 
     #[test]
     fn test_mod_inverse() {
-        assert_eq!(mod_inverse(U2048::from(9)), U2048::from(3));
-        assert_eq!(mod_inverse(U2048::from(6)), U2048::from(11));
+        let params = AccumulatorParams::default();
+        assert_eq!(mod_inverse(&params, U2048::from(9)), U2048::from(3));
+        assert_eq!(mod_inverse(&params, U2048::from(6)), U2048::from(11));
+        assert_eq!(mod_inverse(&params, U2048::from(15)), U2048::from(7));
     }
 
     #[test]
@@ -315,22 +637,114 @@ mod tests {
         assert_eq!(miller_rabin(U2048::from(51456119958243u128)), false);
     }
 
+    #[test]
+    fn test_is_prime() {
+        let primes = ["2", "97", "101", "7919", "2305843009213693951", "618970019642690137449562111",
+                      "170141183460469231731687303715884105727",
+                      "57896044618658097711785492504343953926634992332820282019728792003956564819949",
+                      "115792089237316195423570985008687907853269984665640564039457584007913129639747"];
+        for p in primes.iter() {
+            assert_eq!(is_prime(U2048::from_dec_str(p).unwrap()), true);
+        }
+
+        // Includes Carmichael numbers, strong pseudoprimes to base 2, (strong) Lucas pseudoprimes, a strong
+        // pseudoprime to the first 12 prime bases, RSA-100, a product of two Mersenne primes and the square of a prime.
+        let composites = ["0", "1", "561", "2047", "5459", "5777", "10403", "3215031751", "3825123056546413051",
+                          "318665857834031151167461", "1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139",
+                          "1427247692705959880439315947500961989719490561"];
+        for n in composites.iter() {
+            assert_eq!(is_prime(U2048::from_dec_str(n).unwrap()), false);
+        }
+        let mersenne = U2048::from_dec_str("170141183460469231731687303715884105727").unwrap();
+        assert_eq!(is_prime(mersenne * mersenne), false);
+    }
+
+    #[test]
+    fn test_jacobi() {
+        assert_eq!(jacobi(U2048::from(5), U2048::from(3439601197u64)), -1);
+        assert_eq!(jacobi(U2048::from(2), U2048::from(7)), 1);
+        assert_eq!(jacobi(U2048::from(2), U2048::from(5)), -1);
+        assert_eq!(jacobi(U2048::from(21), U2048::from(35)), 0);
+        assert_eq!(jacobi(U2048::from(1001), U2048::from(9907)), -1);
+    }
+
     #[test]
     fn test_hash_to_prime() {
-        //assert_eq!(hash_to_prime(&[7, 10]), U2048::from(...));
-        // Key values checked: 0, 1, 2
+        let mut params = AccumulatorParams::default();
+        let prime = hash_to_prime(&params, &[7, 10]);
+        assert_eq!(prime, U2048::from_dec_str("238004852282247016525543016900994031331").unwrap());
+        assert_eq!(prime.bits(), 128);
+        assert_eq!(hash_to_prime(&params, &(0 as usize).to_le_bytes()),
+                   U2048::from_dec_str("276912125313351186258214347810186170311").unwrap());
+
+        let (prime, nonce) = hash_to_prime_with_nonce(&params, &[7, 10]);
+        assert_eq!(nonce, 34);
+        assert_eq!(verify_prime_nonce(&params, &[7, 10], nonce), Ok(prime));
+        assert_eq!(verify_prime_nonce(&params, &[7, 10], nonce - 1), Err(Error::NotPrime));
+        assert_eq!(verify_prime_nonce(&params, &[7, 11], nonce), Err(Error::NotPrime));
+
+        params.lambda = 256;
+        let prime = hash_to_prime(&params, &[7, 10]);
+        assert_eq!(prime, U2048::from_dec_str("93632917394313688061496130283681244194645315809550644644784026606818839317707").unwrap());
+        assert_eq!(prime.bits(), 256);
     }
 
     #[test]
     fn test_root_factor() {
-        assert_eq!(root_factor(U2048::from(2), &vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11)]),
+        let params = AccumulatorParams::default();
+        assert_eq!(root_factor(&params, U2048::from(2), &vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11)]),
                    vec![U2048::from(2), U2048::from(8), U2048::from(5), U2048::from(5)]);
     }
 
+    #[test]
+    fn test_quotient_powers() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let elems = vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11), U2048::from(13)];
+        let e = BigUint::from(U2048::from(1) << 300) * U2048::from(12345);
+        let result = quotient_powers(&params, U2048::from(2), &e, &elems);
+        for (elem, (power, r)) in elems.iter().zip(result.iter()) {
+            let (q, expected_r) = e.div_rem_u2048(*elem);
+            assert_eq!(*power, params.exp_big(&U2048::from(2), &q));
+            assert_eq!(*r, expected_r);
+        }
+    }
+
+    #[test]
+    fn test_root_factor_matches_naive() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let elems: Vec<U2048> = (0..9u8).map(|i| hash_to_prime(&params, &[i])).collect();
+        let roots = root_factor(&params, params.generator, &elems);
+        for i in 0..elems.len() {
+            let others: Vec<U2048> = elems.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, elem)| *elem).collect();
+            assert_eq!(roots[i], params.exp_big(&params.generator, &prime_product(&others)));
+        }
+
+        let product = elems.iter().fold(BigUint::one(), |product, elem| product * *elem);
+        assert_eq!(prime_product(&elems), product);
+    }
+
+    #[test]
+    fn test_multi_exp() {
+        let params = AccumulatorParams::default();
+        let values = vec![U2048::from(2), U2048::from(5), U2048::from(6)];
+        let exps = vec![U2048::from(5), U2048::from(7), U2048::from(11)];
+        // 2^(7*11) * 5^(5*11) * 6^(5*7) mod 13
+        assert_eq!(multi_exp(&params, &values, &exps), U2048::from(8));
+        assert_eq!(multi_exp(&params, &values[..1], &exps[..1]), U2048::from(2));
+    }
+
     #[test]
     fn test_prime_product() {
         let elems = vec![U2048::from(2), U2048::from(3), U2048::from(4)];
-        assert_eq!(prime_product(&elems), U2048::from(24));
+        assert_eq!(prime_product(&elems), BigUint::from(U2048::from(24)));
+        assert_eq!(prime_product(&vec![]), BigUint::one());
+
+        // Products wider than a U2048 do not overflow.
+        let product = prime_product(&vec![U2048::from(1) << 1024, U2048::from(1) << 1024, U2048::from(3)]);
+        assert_eq!(product.bits(), 2050);
+        assert_eq!(product.to_u2048(), Err(Error::Overflow));
     }
 
 
@@ -0,0 +1,115 @@
+/// Fiat-Shamir Transcripts
+/// Non-interactive proofs derive their challenges from a transcript instead of hashing the statement
+/// directly. A transcript starts with a domain separation tag, the name of the protocol and the encoded
+/// group parameters, and every message is appended with a label. Challenges therefore differ between
+/// protocols and groups even for identical statements. Challenge primes have twice as many bits as
+/// accumulated elements(capped at 2048 bits) so that a challenge is never an element prime.
+
+use runtime_io::blake2_256;
+use codec::Encode;
+use rstd::prelude::Vec;
+use super::{Group, U2048};
+use crate::subroutines;
+
+/// Tag that separates the transcripts of this crate from other uses of the same hash function.
+pub const DOMAIN_SEPARATOR: &[u8] = b"stateless-blockchain/accumulator/v1";
+
+/// Protocol labels of the proofs and batch verifiers in this crate.
+pub const POE: &[u8] = b"PoE";
+pub const POKE2: &[u8] = b"PoKE2";
+pub const BATCH_MEM_WIT: &[u8] = b"BatchMemWit";
+
+/// Running transcript of a non-interactive proof.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Transcript {
+    state: Vec<u8>,
+    challenge_bits: usize,
+}
+
+impl Transcript {
+    /// Starts a transcript for the given protocol in the given group.
+    pub fn new<G: Group>(protocol: &[u8], group: &G) -> Self {
+        let mut transcript = Transcript {
+            state: Vec::new(),
+            challenge_bits: challenge_bits(group.lambda()),
+        };
+        transcript.append(b"domain", &DOMAIN_SEPARATOR);
+        transcript.append(b"protocol", &protocol);
+        transcript.append(b"group", group);
+        return transcript;
+    }
+
+    /// Appends a labelled message. Labels and messages are length prefixed by the encoding so that
+    /// different sequences of messages never produce the same transcript.
+    pub fn append<T: Encode + ?Sized>(&mut self, label: &[u8], message: &T) {
+        label.encode_to(&mut self.state);
+        message.encode().encode_to(&mut self.state);
+    }
+
+    /// Derives a prime challenge of "challenge_bits" bits and appends it to the transcript.
+    pub fn challenge_prime(&mut self, label: &[u8]) -> U2048 {
+        self.append(b"challenge", &label);
+        let (prime, _) = subroutines::hash_to_prime_bits(&self.state, self.challenge_bits);
+        self.append(label, &prime);
+        return prime;
+    }
+
+    /// Derives a 256 bit scalar challenge and appends it to the transcript.
+    pub fn challenge_scalar(&mut self, label: &[u8]) -> U2048 {
+        self.append(b"challenge", &label);
+        let scalar = U2048::from_little_endian(&blake2_256(&self.state));
+        self.append(label, &scalar);
+        return scalar;
+    }
+}
+
+/// Bit size of challenge primes for elements of "lambda" bits.
+pub fn challenge_bits(lambda: u32) -> usize {
+    return core::cmp::min(2 * lambda as usize, 2048);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AccumulatorParams;
+    use crate::class_group::ClassGroup;
+
+    #[test]
+    fn test_domain_separation() {
+        let params = AccumulatorParams::default();
+        let mut poe = Transcript::new(POE, &params);
+        let mut poke2 = Transcript::new(POKE2, &params);
+        poe.append(b"u", &U2048::from(2));
+        poke2.append(b"u", &U2048::from(2));
+        let l = poe.challenge_prime(b"l");
+        assert_eq!(l == poke2.challenge_prime(b"l"), false);
+
+        // The same transcript always gives the same challenges.
+        let mut again = Transcript::new(POE, &params);
+        again.append(b"u", &U2048::from(2));
+        assert_eq!(again.challenge_prime(b"l"), l);
+
+        // Labels and group parameters are bound.
+        let mut relabelled = Transcript::new(POE, &params);
+        relabelled.append(b"w", &U2048::from(2));
+        assert_eq!(relabelled.challenge_prime(b"l") == l, false);
+        let mut other_group = Transcript::new(POE, &AccumulatorParams::new(U2048::from(23), U2048::from(2), crate::LAMBDA).unwrap());
+        other_group.append(b"u", &U2048::from(2));
+        assert_eq!(other_group.challenge_prime(b"l") == l, false);
+    }
+
+    #[test]
+    fn test_challenge_size() {
+        let params = AccumulatorParams::default();
+        let mut transcript = Transcript::new(POE, &params);
+        let l = transcript.challenge_prime(b"l");
+        assert_eq!(l.bits(), 2 * crate::LAMBDA as usize);
+        assert_eq!(subroutines::is_prime(l), true);
+        assert_eq!(l.bits() > subroutines::hash_to_prime(&params, &[0]).bits(), true);
+
+        let group = ClassGroup::from_seed(b"test_challenge_size", 128);
+        let mut transcript = Transcript::new(POKE2, &group);
+        assert_eq!(transcript.challenge_prime(b"l").bits(), 2 * crate::LAMBDA as usize);
+        assert_eq!(challenge_bits(2048), 2048);
+    }
+}
@@ -2,61 +2,172 @@
 
 use crate::subroutines;
 use crate::proofs;
+use crate::transcript::{self, Transcript};
 use rstd::prelude::Vec;
-use super::U2048;
+use super::{AggNonMemWit, BigUint, Error, Group, I2048, U2048};
 
 /// Given an old state, the product of a set of elements that have been added, and a single element from that
-/// set, returns the witness for that element.
+/// set, returns the witness for that element. "elem" may also be the product of a subset of the elements.
 /// NOTE: "old_state" represents the state *before* the elements are added.
 /// This function will likely be used by an online user.
-pub fn mem_wit_create(old_state: U2048, agg: U2048, elem: U2048) -> Option<U2048> {
-    if agg % elem != U2048::from(0) {
-        return None;
+pub fn mem_wit_create<G: Group>(group: &G, old_state: G::Elem, agg: &BigUint, elem: &BigUint) -> Result<G::Elem, Error> {
+    if elem.is_zero() {
+        return Err(Error::NotDivisible);
     }
-    let quotient = agg / elem;
-    return Some(subroutines::mod_exp(old_state, quotient, U2048::from_dec_str(super::MODULUS).unwrap()));
+    let (quotient, remainder) = agg.div_rem(elem);
+    if !remainder.is_zero() {
+        return Err(Error::NotDivisible);
+    }
+    return Ok(group.exp_big(&old_state, &quotient));
 }
 
 /// Verify the witness of an element.
-pub fn verify_mem_wit(state: U2048, witness: U2048, elem: U2048) -> bool {
-    let result = subroutines::mod_exp(witness, elem, U2048::from_dec_str(super::MODULUS).unwrap());
+pub fn verify_mem_wit<G: Group>(group: &G, state: G::Elem, witness: G::Elem, elem: U2048) -> bool {
+    let result = group.exp(&witness, elem);
     return result == state;
 }
 
+/// Bit size of the random coefficients of verify_mem_wits_batch.
+pub const BATCH_COEFFICIENT_BITS: usize = 128;
+
+/// Verifies many membership witnesses against the same state at once(Ex: the spent UTXOs of a block). Uses
+/// small-exponent batching: checks that prod (witness_i^elem_i)^rho_i = state^(rho_1 + ... + rho_n) where the
+/// 128 bit coefficients rho_i are derived from a transcript of all of the inputs, and evaluates the left side
+/// with Group::simultaneous_exp so that the witnesses share one chain of squarings.
+/// NOTE: Like any batch test in a group of unknown order, a witness that is only off by an element of order two
+/// (Ex: -witness in an RSA group) may be accepted. The element is still a member since the corrected witness is
+/// valid, but callers that pass witnesses on to batch_delete should expect it to reject them.
+pub fn verify_mem_wits_batch<G: Group>(group: &G, state: G::Elem, elems: &[(U2048, G::Elem)]) -> bool {
+    let mut transcript = Transcript::new(transcript::BATCH_MEM_WIT, group);
+    transcript.append(b"state", &state);
+    transcript.append(b"elems", elems);
+
+    let mask = (U2048::from(1) << BATCH_COEFFICIENT_BITS) - U2048::from(1);
+    let mut bases = Vec::with_capacity(elems.len());
+    let mut exps = Vec::with_capacity(elems.len());
+    let mut rho_sum = U2048::from(0);
+    for (elem, witness) in elems {
+        let rho = transcript.challenge_scalar(b"rho") & mask;
+        bases.push(*witness);
+        exps.push(BigUint::from(*elem) * rho);
+        rho_sum = rho_sum + rho;
+    }
+    return group.simultaneous_exp(&bases, &exps) == group.exp(&state, rho_sum);
+}
+
 /// Updates a membership witness based on untracked additions and deletions. Algorithm is based on
 /// section 3.2 of the paper titled "Dynamic Accumulators and Applications to Efficient Revocation of
 /// Anonymous Credentials". Note that "additions" represent the product of the added elements
 /// and "deletions" represents the product of the deleted elements.
-/// NOTE: Does not do any error checking on unwrap.
-pub fn update_mem_wit(elem: U2048, mut witness: U2048, new_state: U2048, additions: U2048, deletions: U2048) -> U2048 {
+pub fn update_mem_wit<G: Group>(group: &G, elem: U2048, mut witness: G::Elem, new_state: G::Elem, additions: &BigUint, deletions: &BigUint) -> Result<G::Elem, Error> {
     // Handle added elems
-    witness = subroutines::mod_exp(witness, additions, U2048::from_dec_str(super::MODULUS).unwrap());
+    witness = group.exp_big(&witness, additions);
 
     // Handle deleted elems
-    witness = subroutines::shamir_trick(witness, new_state, elem, deletions).unwrap();
-    return witness;
+    witness = subroutines::shamir_trick(group, new_state, witness, deletions, elem)?;
+    return Ok(witness);
 }
 
+/// Updates the membership witnesses of many elements at once(Ex: all UTXOs of a wallet after a block).
+/// witnesses[i] is the witness of elems[i] and the result of each update is reported separately. The
+/// witnesses that agree on the previous state share the validity check and the large exponentiations by
+/// "additions" and "deletions" through subroutines::quotient_powers. The remaining ones are updated
+/// individually with update_mem_wit. Fails if there is not exactly one witness per element.
+pub fn batch_update_mem_wits<G: Group>(group: &G, elems: &[U2048], witnesses: &[G::Elem], new_state: G::Elem,
+                                       additions: &BigUint, deletions: &BigUint) -> Result<Vec<Result<G::Elem, Error>>, Error> {
+    if elems.len() != witnesses.len() {
+        return Err(Error::LengthMismatch);
+    }
+    let mut results = Vec::with_capacity(elems.len());
+    if elems.is_empty() {
+        return Ok(results);
+    }
+
+    // The previous state implied by the first witness.
+    let old_state = group.exp(&witnesses[0], elems[0]);
+    let batch: Vec<usize> = (0..elems.len())
+        .filter(|&i| group.exp(&witnesses[i], elems[i]) == old_state)
+        .collect();
+    let batch_elems: Vec<U2048> = batch.iter().map(|&i| elems[i]).collect();
+
+    // Same check as shamir_trick: new_state^deletions = (witness^additions)^elem = old_state^additions.
+    let valid = group.exp_big(&new_state, deletions) == group.exp_big(&old_state, additions);
+    let mut added = Vec::new();
+    let mut deleted = Vec::new();
+    if valid {
+        added = subroutines::quotient_powers(group, old_state, additions, &batch_elems);
+        deleted = subroutines::quotient_powers(group, new_state, deletions, &batch_elems);
+    }
+
+    let mut position = 0;
+    for i in 0..elems.len() {
+        if position >= batch.len() || batch[position] != i {
+            results.push(update_mem_wit(group, elems[i], witnesses[i], new_state, additions, deletions));
+            continue;
+        }
+        if !valid {
+            results.push(Err(Error::InvalidWitness));
+            position += 1;
+            continue;
+        }
+
+        // witness^additions = witness^(additions mod elem) * old_state^(additions / elem)
+        let (old_state_q, additions_r) = added[position];
+        let witness = group.op(&group.exp(&witnesses[i], additions_r), &old_state_q);
+
+        // Shamir's trick with the precomputed new_state^(deletions / elem).
+        let (new_state_q, deletions_r) = deleted[position];
+        results.push(subroutines::bezout(deletions_r, elems[i]).map(|pair| {
+            let new_state_b = group.op(&group.exp_signed(&new_state, pair.coefficient_b),
+                                       &group.exp_signed(&new_state_q, -pair.coefficient_a));
+            return group.op(&new_state_b, &group.exp_signed(&witness, pair.coefficient_a));
+        }));
+        position += 1;
+    }
+    return Ok(results);
+}
 
 /// Takes two elements + membership witnesses and returns the aggregated witness and aggregated proof.
-/// NOTE: Does very little error checking (Ex: Does not do any error checking on unwrap).
-pub fn agg_mem_wit(state: U2048, witness_x: U2048, witness_y: U2048, x: U2048, y: U2048) -> (U2048, U2048) {
-    let aggregated = subroutines::shamir_trick(witness_x, witness_y, x, y).unwrap();
-    let proof = proofs::poe(aggregated, subroutines::mul_mod(x, y, U2048::from_dec_str(super::MODULUS).unwrap()), state);
-    return (aggregated, proof);
+pub fn agg_mem_wit<G: Group>(group: &G, state: G::Elem, witness_x: G::Elem, witness_y: G::Elem, x: U2048, y: U2048) -> Result<(G::Elem, G::Elem), Error> {
+    return agg_mem_wits(group, state, &[(x, witness_x), (y, witness_y)]);
+}
+
+/// Takes any number of elements + membership witnesses(Ex: the inputs of a transaction) and returns a single
+/// witness for the product of the elements together with a proof of exponentiation. Checked by verify_agg_mem_wit.
+/// Fails if the witnesses are inconsistent with each other or if an element appears twice. Witnesses that agree
+/// with each other but not with "state" yield a proof that does not verify.
+pub fn agg_mem_wits<G: Group>(group: &G, state: G::Elem, elems: &[(U2048, G::Elem)]) -> Result<(G::Elem, G::Elem), Error> {
+    if elems.is_empty() {
+        return Err(Error::Empty);
+    }
+    let (x, mut aggregated) = elems[0];
+    let mut x_agg = BigUint::from(x);
+    for i in 1..elems.len() {
+        let (x, witness) = elems[i];
+        aggregated = subroutines::shamir_trick(group, aggregated, witness, &x_agg, x)?;
+        x_agg = x_agg * x;
+    }
+    let proof = proofs::poe(group, aggregated, &x_agg, state);
+    return Ok((aggregated, proof));
 }
 
 /// Verifies that a membership witness + proof for a set of accumulator elements are valid. Acts as a
 /// wrapper for the proof of exponentiation verifier.
-pub fn verify_agg_mem_wit(state: U2048, agg_elems: U2048, witness: U2048, proof: U2048) -> bool {
-    return proofs::verify_poe(witness, agg_elems, state, proof);
+pub fn verify_agg_mem_wit<G: Group>(group: &G, state: G::Elem, agg_elems: &BigUint, witness: G::Elem, proof: G::Elem) -> bool {
+    return proofs::verify_poe(group, witness, agg_elems, state, proof);
+}
+
+/// Converts a membership witness for one of two merged accumulators into a witness for their union(see
+/// crate::union). "other_agg" is the product of the elements of the other accumulator.
+pub fn union_mem_wit<G: Group>(group: &G, witness: G::Elem, other_agg: &BigUint) -> G::Elem {
+    return group.exp_big(&witness, other_agg);
 }
 
 /// Creates individual membership witnesses. Acts as a wrapper for the RootFactor subroutine.
 /// NOTE: "old_state" represents the state *before* the elements are added.
 /// This function will most likely be used by a service provider.
-pub fn create_all_mem_wit(old_state: U2048, new_elems: &[U2048]) -> Vec<U2048> {
-    return subroutines::root_factor(old_state, new_elems);
+pub fn create_all_mem_wit<G: Group>(group: &G, old_state: G::Elem, new_elems: &[U2048]) -> Vec<G::Elem> {
+    return subroutines::root_factor(group, old_state, new_elems);
 }
 
 /// Below contains all of the non-membership witness functions required for vector commitments.
@@ -65,89 +176,212 @@ pub fn create_all_mem_wit(old_state: U2048, new_elems: &[U2048]) -> Vec<U2048> {
 /// inputted.
 
 /// Creates a non-membership witness relative to some previous state. The current state should equal "old_state"
-/// raised to the "agg_elems" power(represents product of added elements). The second value of the
-/// tuple is the sign of the first value since the Bezout coefficient may be negative.
-/// NOTE: Function assumes that "elem" is not contained in "agg_elems"
-pub fn non_mem_wit_create(mut old_state: U2048, agg_elems: U2048, elem: U2048) -> (U2048, bool, U2048) {
-    let pair = subroutines::bezout(agg_elems, elem).unwrap();
+/// raised to the "agg_elems" power(represents product of added elements). The witness is a pair (a, B) where the
+/// Bezout coefficient a may be negative. Fails if "elem" is contained in "agg_elems".
+pub fn non_mem_wit_create<G: Group>(group: &G, old_state: G::Elem, agg_elems: &BigUint, elem: U2048) -> Result<(I2048, G::Elem), Error> {
+    // Reduce agg_elems so that the Bezout coefficients fit in an I2048. If agg_elems = q*elem + r and
+    // a*r + b*elem = 1, then a*agg_elems + (b - a*q)*elem = 1.
+    let (q, r) = agg_elems.div_rem_u2048(elem);
+    let pair = subroutines::bezout(r, elem)?;
+    let B = group.op(&group.exp_signed(&old_state, pair.coefficient_b),
+                     &group.exp_signed(&group.exp_big(&old_state, &q), -pair.coefficient_a));
+    return Ok((pair.coefficient_a, B));
+}
+
+/// Verifies a non-membership witness. "state" represents the current state.
+pub fn verify_non_mem_wit<G: Group>(group: &G, old_state: G::Elem, state: G::Elem, witness: (I2048, G::Elem), elem: U2048) -> bool {
+    let (a, B) = witness;
+    let exp_1 = group.exp_signed(&state, a);
+    let exp_2 = group.exp(&B, elem);
+    return group.op(&exp_1, &exp_2) == old_state;
+}
 
-    if pair.sign_b {
-        old_state = subroutines::mod_inverse(old_state);
+/// Updates a non-membership witness for "elem" after untracked additions and deletions. "state" is the state
+/// that the witness was created for and "new_state" is the state after the elements are deleted and added. Note
+/// that "additions" represents the product of the added elements and "deletions" represents the product of the
+/// deleted elements. Algorithm is based on section 3.2 of the paper titled "Universal Accumulators with Efficient
+/// Nonmembership Proofs". Fails if "elem" is one of the added elements.
+pub fn update_non_mem_wit<G: Group>(group: &G, elem: U2048, witness: (I2048, G::Elem), state: G::Elem, new_state: G::Elem,
+                                    additions: &BigUint, deletions: &BigUint) -> Result<(I2048, G::Elem), Error> {
+    let (a, B) = reduce_non_mem_wit(group, elem, witness, state);
+    let a = a.magnitude();
+
+    // alpha is the inverse of the additions modulo elem, so additions*alpha = 1 + t*elem.
+    let (_, s) = additions.div_rem_u2048(elem);
+    let alpha = subroutines::bezout(s, elem)?.coefficient_a.rem_euclid(elem);
+    let (t, _) = (additions.clone() * alpha).div_rem_u2048(elem);
+
+    // Since new_state^deletions = state^additions, splitting a*deletions*alpha = k*elem + a_new gives
+    // new_state^a_new * (B * state^(-a*t) * new_state^k)^elem = state^a * B^elem.
+    let (k, a_new) = (deletions.clone() * a * alpha).div_rem_u2048(elem);
+    let B_new = group.op(&group.op(&B, &group.exp_signed(&group.exp_big(&state, &t), -I2048::from(a))),
+                         &group.exp_big(&new_state, &k));
+    return Ok((I2048::from(a_new), B_new));
+}
+
+/// Reduces the coefficient of a non-membership witness into [0, elem) so that it does not grow with each update.
+fn reduce_non_mem_wit<G: Group>(group: &G, elem: U2048, witness: (I2048, G::Elem), state: G::Elem) -> (I2048, G::Elem) {
+    let (a, B) = witness;
+    let a_reduced = I2048::from(a.rem_euclid(elem));
+    let k = (a - a_reduced) / I2048::from(elem);
+    return (a_reduced, group.op(&B, &group.exp_signed(&state, k)));
+}
+
+/// Creates a constant-size non-membership proof for a set of elements relative to some previous state. "elems" is
+/// the product of the elements and "state" should equal "old_state" raised to "agg_elems". Unlike non_mem_wit_create,
+/// the Bezout coefficient is not part of the proof, so the size of the proof does not grow with the number of
+/// elements. Based on NonMemWitCreate* from section 4.2 of the paper. Fails if any of the elements is a member.
+/// The product of the elements may be of any width.
+pub fn agg_non_mem_wit<G: Group>(group: &G, old_state: G::Elem, state: G::Elem, agg_elems: &BigUint, elems: &BigUint) -> Result<AggNonMemWit<G::Elem>, Error> {
+    // Bezout coefficients with a non-negative a: a*agg_elems = k*elems + 1, so a*agg_elems + (-k)*elems = 1.
+    let a = agg_elems.mod_inverse(elems)?;
+    let (k, remainder) = (agg_elems.clone() * a.clone()).div_rem(elems);
+    let mut B = group.inverse(&group.exp_big(&old_state, &k));
+    if remainder.is_zero() {
+        // Only happens for elems = 1, where a = 0 and the coefficient of elems is 1.
+        B = group.op(&B, &old_state);
     }
+    let V = group.exp_big(&state, &a);
 
-    let B = subroutines::mod_exp(old_state, U2048::from(pair.coefficient_b), U2048::from_dec_str(super::MODULUS).unwrap());
-    return (pair.coefficient_a, pair.sign_a, B);
+    // Prove knowledge of a such that state^a = V and that B^elems = old_state/V.
+    let (z, Q_v, r) = proofs::poke2_big(group, state, &a, V);
+    let Q_g = proofs::poe(group, B, elems, group.op(&old_state, &group.inverse(&V)));
+    let q = proofs::pokcr(group, &[Q_v, Q_g]);
+    return Ok(AggNonMemWit { v: V, b: B, z, r, q });
 }
 
-/// Verifies a non-membership witness. "state" represents the current state.
-pub fn verify_non_mem_wit(old_state: U2048, mut state: U2048, witness: (U2048, bool, U2048), elem: U2048) -> bool {
-    let (a, sign_a, B) = witness;
+/// Verifies a constant-size non-membership proof. "state" represents the current state.
+pub fn verify_agg_non_mem_wit<G: Group>(group: &G, old_state: G::Elem, state: G::Elem, proof: AggNonMemWit<G::Elem>, elems: &BigUint) -> bool {
+    let AggNonMemWit { v: V, b: B, z, r, q } = proof;
+    let g = group.generator();
 
-    if sign_a {
-        state = subroutines::mod_inverse(state);
+    // Recover the values whose roots were aggregated: (state * g^alpha)^q_v = V * z^alpha / (state * g^alpha)^r
+    // for the NI-PoKE2 and B^q_g = (old_state/V) / B^(elems mod l_g) for the NI-PoE.
+    let (l_v, alpha) = proofs::poke2_challenge(group, state, V, z);
+    if r >= l_v {
+        return false;
     }
+    let base = group.op(&state, &group.exp(&g, alpha));
+    let w_v = group.op(&group.op(&V, &group.exp(&z, alpha)), &group.inverse(&group.exp(&base, r)));
 
-    let exp_1 = subroutines::mod_exp(state, U2048::from(a), U2048::from_dec_str(super::MODULUS).unwrap());
-    let exp_2 = subroutines::mod_exp(B, elem, U2048::from_dec_str(super::MODULUS).unwrap());
+    let target = group.op(&old_state, &group.inverse(&V));
+    let l_g = proofs::poe_challenge(group, B, elems, target);
+    if l_g == l_v {
+        return false;
+    }
+    let (_, r_g) = elems.div_rem_u2048(l_g);
+    let w_g = group.op(&target, &group.inverse(&group.exp(&B, r_g)));
 
-    return subroutines::mul_mod(exp_1, exp_2, U2048::from_dec_str(super::MODULUS).unwrap()) == old_state;
+    return proofs::verify_pokcr(group, &[w_v, w_g], &[l_v, l_g], q);
 }
 
-pub fn update_non_mem_wit() {}
-
 /// OPTIONAL FUNCTION.
 /// Given the current state, the previous state, the product of the added elements, and a subset of
 /// those elements, creates a witness for thoise elements.
-pub fn mem_wit_create_star(cur_state: U2048, old_state: U2048, agg: U2048, new_elems: Vec<U2048>) -> (U2048, U2048) {
+pub fn mem_wit_create_star<G: Group>(group: &G, cur_state: G::Elem, old_state: G::Elem, agg: &BigUint, new_elems: Vec<U2048>) -> Result<(G::Elem, G::Elem), Error> {
     let product = subroutines::prime_product(&new_elems);
-    let witness = mem_wit_create(old_state, agg, product).unwrap();
-    let proof = proofs::poe(witness, product, cur_state);
-    return (witness, proof);
+    let witness = mem_wit_create(group, old_state, agg, &product)?;
+    let proof = proofs::poe(group, witness, &product, cur_state);
+    return Ok((witness, proof));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::batch_add;
+    use crate::{batch_add, batch_delete, AccumulatorParams};
+    use crate::class_group::ClassGroup;
+
+    /// Shorthand for small exponents.
+    fn big(value: u64) -> BigUint {
+        return BigUint::from(U2048::from(value));
+    }
 
     #[test]
     fn test_mem_wit_create() {
-        assert_eq!(mem_wit_create(U2048::from(2), U2048::from(1155), U2048::from(3)).unwrap(), U2048::from(2));
-        assert_eq!(mem_wit_create(U2048::from(2), U2048::from(1155), U2048::from(5)).unwrap(), U2048::from(8));
-        assert_eq!(mem_wit_create(U2048::from(2), U2048::from(1155), U2048::from(7)).unwrap(), U2048::from(5));
-        assert_eq!(mem_wit_create(U2048::from(2), U2048::from(1155),U2048::from(11)).unwrap(), U2048::from(5));
-        assert_eq!(mem_wit_create(U2048::from(2), U2048::from(1155),U2048::from(4)).is_none(), true);
+        let params = AccumulatorParams::default();
+        assert_eq!(mem_wit_create(&params, U2048::from(2), &big(1155), &big(3)).unwrap(), U2048::from(2));
+        assert_eq!(mem_wit_create(&params, U2048::from(2), &big(1155), &big(5)).unwrap(), U2048::from(8));
+        assert_eq!(mem_wit_create(&params, U2048::from(2), &big(1155), &big(7)).unwrap(), U2048::from(5));
+        assert_eq!(mem_wit_create(&params, U2048::from(2), &big(1155), &big(11)).unwrap(), U2048::from(5));
+        assert_eq!(mem_wit_create(&params, U2048::from(2), &big(1155), &big(4)), Err(Error::NotDivisible));
+    }
+
+    #[test]
+    fn test_verify_mem_wits_batch() {
+        let group = ClassGroup::from_seed(b"test_verify_mem_wits_batch", 128);
+        let elems: Vec<U2048> = (0..5).map(|i| subroutines::hash_to_prime(&group, &[i])).collect();
+        let (state, _, _) = batch_add(&group, group.generator(), &elems);
+        let witnesses = create_all_mem_wit(&group, group.generator(), &elems);
+        let mut pairs: Vec<(U2048, crate::class_group::Form)> = elems.iter().cloned().zip(witnesses.iter().cloned()).collect();
+        assert_eq!(verify_mem_wits_batch(&group, state, &pairs), true);
+        assert_eq!(verify_mem_wits_batch(&group, state, &pairs[..1]), true);
+        assert_eq!(verify_mem_wits_batch(&group, state, &[]), true);
+        assert_eq!(verify_mem_wits_batch(&group, group.generator(), &pairs), false);
+
+        // A single invalid witness fails the whole batch.
+        pairs[3].1 = witnesses[2];
+        assert_eq!(verify_mem_wits_batch(&group, state, &pairs), false);
+        pairs[3] = (elems[2], witnesses[3]);
+        assert_eq!(verify_mem_wits_batch(&group, state, &pairs), false);
+
+        let params = AccumulatorParams::default();
+        assert_eq!(verify_mem_wits_batch(&params, U2048::from(8), &[(U2048::from(3), U2048::from(2)), (U2048::from(5), U2048::from(8))]), true);
     }
 
     #[test]
     fn test_agg_mem_wit() {
-        let (aggregate, proof) = agg_mem_wit(U2048::from(8), U2048::from(6), U2048::from(8),U2048::from(3), U2048::from(5));
+        let params = AccumulatorParams::default();
+        let (aggregate, proof) = agg_mem_wit(&params, U2048::from(8), U2048::from(6), U2048::from(8),U2048::from(3), U2048::from(5)).unwrap();
         assert_eq!(aggregate, U2048::from(2));
-        assert_eq!(verify_agg_mem_wit(U2048::from(8), U2048::from(15), aggregate, proof), true);
+        assert_eq!(verify_agg_mem_wit(&params, U2048::from(8), &big(15), aggregate, proof), true);
+    }
+
+    #[test]
+    fn test_agg_mem_wits() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let elems = vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11)];
+        let witnesses = create_all_mem_wit(&params, U2048::from(2), &elems);
+        let (state, agg, _) = batch_add(&params, U2048::from(2), &elems);
+        let pairs: Vec<(U2048, U2048)> = elems.iter().cloned().zip(witnesses.iter().cloned()).collect();
+
+        let (aggregate, proof) = agg_mem_wits(&params, state, &pairs[..3]).unwrap();
+        assert_eq!(aggregate, params.exp(&U2048::from(2), U2048::from(11)));
+        assert_eq!(verify_agg_mem_wit(&params, state, &big(3 * 5 * 7), aggregate, proof), true);
+        assert_eq!(verify_agg_mem_wit(&params, state, &big(3 * 5 * 11), aggregate, proof), false);
+
+        let (aggregate, proof) = agg_mem_wits(&params, state, &pairs).unwrap();
+        assert_eq!(verify_agg_mem_wit(&params, state, &agg, aggregate, proof), true);
+        assert_eq!(agg_mem_wits(&params, state, &pairs[..1]), Ok((witnesses[0], proofs::poe(&params, witnesses[0], &big(3), state))));
+        assert_eq!(agg_mem_wits(&params, state, &[pairs[0], pairs[0]]), Err(Error::NotCoprime));
+        assert_eq!(agg_mem_wits(&params, state, &[]), Err(Error::Empty));
     }
 
     #[test]
     fn test_verify_agg_mem_wit() {
-        let proof = proofs::poe(U2048::from(2), U2048::from(12123), U2048::from(8));
-        assert_eq!(verify_agg_mem_wit(U2048::from(8), U2048::from(12123), U2048::from(2), proof), true);
-        assert_eq!(verify_agg_mem_wit(U2048::from(7), U2048::from(12123), U2048::from(2), proof), false);
+        let params = AccumulatorParams::default();
+        let proof = proofs::poe(&params, U2048::from(2), &big(12123), U2048::from(8));
+        assert_eq!(verify_agg_mem_wit(&params, U2048::from(8), &big(12123), U2048::from(2), proof), true);
+        assert_eq!(verify_agg_mem_wit(&params, U2048::from(7), &big(12123), U2048::from(2), proof), false);
     }
 
     #[test]
     fn test_update_mem_wit() {
-        let deletions = U2048::from(15);
-        let additions = U2048::from(77);
+        let params = AccumulatorParams::default();
+        let deletions = big(15);
+        let additions = big(77);
 
         let elem = U2048::from(12131);
         let witness = U2048::from(8);
         let new_state = U2048::from(11);
 
-        assert_eq!(update_mem_wit(elem, witness, new_state, additions, deletions), U2048::from(6));
+        assert_eq!(update_mem_wit(&params, elem, witness, new_state, &additions, &deletions), Ok(U2048::from(6)));
     }
 
     #[test]
     fn test_create_all_mem_wit() {
-        assert_eq!(create_all_mem_wit(U2048::from(2), &vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11)]),
+        let params = AccumulatorParams::default();
+        assert_eq!(create_all_mem_wit(&params, U2048::from(2), &vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11)]),
                    vec![U2048::from(2), U2048::from(8), U2048::from(5), U2048::from(5)]);
     }
 
@@ -155,26 +389,227 @@ mod tests {
 
     #[test]
     fn test_non_mem_wit() {
-        let (a, sign_a, B) = non_mem_wit_create(U2048::from(2), U2048::from(105), U2048::from(11));
+        let params = AccumulatorParams::default();
+        let (a, B) = non_mem_wit_create(&params, U2048::from(2), &big(105), U2048::from(11)).unwrap();
+
+        assert_eq!(verify_non_mem_wit(&params, U2048::from(2), U2048::from(5), (a, B), U2048::from(11)), true);
+        assert_eq!(verify_non_mem_wit(&params, U2048::from(2), U2048::from(6), (a, B), U2048::from(11)), false);
+        assert_eq!(verify_non_mem_wit(&params, U2048::from(2), U2048::from(5), (a, B), U2048::from(5)), false);
+    }
 
-        assert_eq!(verify_non_mem_wit(U2048::from(2), U2048::from(5), (a, sign_a, B), U2048::from(11)), true);
-        assert_eq!(verify_non_mem_wit(U2048::from(2), U2048::from(6), (a, sign_a, B), U2048::from(11)), false);
-        assert_eq!(verify_non_mem_wit(U2048::from(2), U2048::from(5), (a, sign_a, B), U2048::from(5)), false);
+    #[test]
+    fn test_update_non_mem_wit() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let generator = params.generator;
+        let elem = U2048::from(11);
+        let (state, agg, _) = batch_add(&params, generator, &vec![U2048::from(3), U2048::from(5), U2048::from(7)]);
+        let witness = non_mem_wit_create(&params, generator, &agg, elem).unwrap();
+
+        // Add 13 and 17 and delete 5.
+        let additions = big(13 * 17);
+        let deletions = big(5);
+        let new_agg = big(3 * 7 * 13 * 17);
+        let new_state = params.exp_big(&generator, &new_agg);
+
+        let updated = update_non_mem_wit(&params, elem, witness, state, new_state, &additions, &deletions).unwrap();
+        assert_eq!(verify_non_mem_wit(&params, generator, new_state, updated, elem), true);
+        assert_eq!(verify_non_mem_wit(&params, generator, state, updated, elem), false);
+
+        // Compare against a witness recomputed from scratch.
+        let fresh = non_mem_wit_create(&params, generator, &new_agg, elem).unwrap();
+        assert_eq!(updated, reduce_non_mem_wit(&params, elem, fresh, new_state));
+
+        // Only additions, then only deletions.
+        let updated = update_non_mem_wit(&params, elem, witness, state, params.exp(&state, U2048::from(19)), &big(19), &big(1)).unwrap();
+        let fresh = non_mem_wit_create(&params, generator, &(agg.clone() * U2048::from(19)), elem).unwrap();
+        assert_eq!(updated, reduce_non_mem_wit(&params, elem, fresh, params.exp(&state, U2048::from(19))));
+
+        let updated = update_non_mem_wit(&params, elem, witness, state, params.exp(&generator, U2048::from(3)), &big(1), &big(35)).unwrap();
+        assert_eq!(verify_non_mem_wit(&params, generator, params.exp(&generator, U2048::from(3)), updated, elem), true);
+
+        // The element itself cannot be added.
+        assert_eq!(update_non_mem_wit(&params, elem, witness, state, params.exp(&state, elem), &BigUint::from(elem), &big(1)), Err(Error::NotCoprime));
+    }
+
+    #[test]
+    fn test_agg_non_mem_wit() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let old_state = params.generator;
+        let added: Vec<U2048> = vec![3, 5, 7, 11].into_iter().map(|p| subroutines::hash_to_prime(&params, &[p])).collect();
+        let (state, agg, _) = batch_add(&params, old_state, &added);
+
+        let excluded: Vec<U2048> = vec![13, 17, 19].into_iter().map(|p| subroutines::hash_to_prime(&params, &[p])).collect();
+        let elems = subroutines::prime_product(&excluded);
+        let proof = agg_non_mem_wit(&params, old_state, state, &agg, &elems).unwrap();
+        assert_eq!(verify_agg_non_mem_wit(&params, old_state, state, proof, &elems), true);
+
+        // The proof only contains group elements and a residue below the challenge regardless of the number of elements.
+        assert_eq!(proof.r.bits() <= crate::transcript::challenge_bits(crate::LAMBDA), true);
+
+        // Wrong elements, wrong state and tampered proofs
+        assert_eq!(verify_agg_non_mem_wit(&params, old_state, state, proof, &(BigUint::from(excluded[0]) * excluded[1])), false);
+        assert_eq!(verify_agg_non_mem_wit(&params, old_state, params.exp(&state, U2048::from(3)), proof, &elems), false);
+        assert_eq!(verify_agg_non_mem_wit(&params, old_state, state, AggNonMemWit { r: proof.r + U2048::from(1), ..proof }, &elems), false);
+        assert_eq!(verify_agg_non_mem_wit(&params, old_state, state, AggNonMemWit { q: params.op(&proof.q, &U2048::from(2)), ..proof }, &elems), false);
+
+        // Members cannot be excluded.
+        assert_eq!(agg_non_mem_wit(&params, old_state, state, &agg, &(BigUint::from(added[1]) * excluded[0])), Err(Error::NotCoprime));
+
+        // The product of the excluded elements may exceed 2048 bits.
+        let excluded: Vec<U2048> = (20..40u8).map(|p| subroutines::hash_to_prime(&params, &[p])).collect();
+        let elems = subroutines::prime_product(&excluded);
+        assert_eq!(elems.to_u2048(), Err(Error::Overflow));
+        let proof = agg_non_mem_wit(&params, old_state, state, &agg, &elems).unwrap();
+        assert_eq!(verify_agg_non_mem_wit(&params, old_state, state, proof, &elems), true);
+        assert_eq!(verify_agg_non_mem_wit(&params, old_state, state, proof, &subroutines::prime_product(&excluded[1..])), false);
     }
 
     #[test]
     fn test_mem_wit_create_star() {
+        let params = AccumulatorParams::default();
         let old_state = U2048::from(2);
         let new_elems = vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11), U2048::from(17)];
-        let (new_state, agg, _) = batch_add(old_state, &new_elems);
+        let (new_state, agg, _) = batch_add(&params, old_state, &new_elems);
 
         let subset = vec![U2048::from(5), U2048::from(11), U2048::from(17)];
         let subset_product = subroutines::prime_product(&subset);
-        let (witness, proof) = mem_wit_create_star(new_state, old_state, agg, subset);
+        let (witness, proof) = mem_wit_create_star(&params, new_state, old_state, &agg, subset).unwrap();
 
         assert_eq!(witness, U2048::from(5));
-        assert_eq!(proofs::verify_poe(witness, subset_product, new_state, proof), true);
+        assert_eq!(proofs::verify_poe(&params, witness, &subset_product, new_state, proof), true);
     }
 
+    #[test]
+    fn test_wide_batches() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let old_state = params.generator;
+        // The product of 20 128-bit primes no longer fits in a U2048.
+        let elems: Vec<U2048> = (0..20u8).map(|i| subroutines::hash_to_prime(&params, &[i])).collect();
+        let (state, agg, proof) = batch_add(&params, old_state, &elems);
+        assert_eq!(agg.bits() > 2048, true);
+        assert_eq!(proofs::verify_poe(&params, old_state, &agg, state, proof), true);
+
+        let witnesses = create_all_mem_wit(&params, old_state, &elems);
+        let deletions: Vec<(U2048, U2048)> = elems.iter().cloned().zip(witnesses.iter().cloned()).skip(1).collect();
+        let (new_state, deleted, proof) = batch_delete(&params, state, &deletions).unwrap();
+        assert_eq!(deleted.bits() > 2048, true);
+        assert_eq!(new_state, params.exp(&old_state, elems[0]));
+        assert_eq!(proofs::verify_poe(&params, new_state, &deleted, state, proof), true);
+
+        let updated = update_mem_wit(&params, elems[0], witnesses[0], new_state, &BigUint::one(), &deleted).unwrap();
+        assert_eq!(updated, old_state);
+        let updated = update_mem_wit(&params, elems[0], updated, state, &deleted, &BigUint::one()).unwrap();
+        assert_eq!(updated, witnesses[0]);
+    }
+
+    #[test]
+    fn test_batch_update_mem_wits() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let generator = params.generator;
+        let elems: Vec<U2048> = (0..8u8).map(|i| subroutines::hash_to_prime(&params, &[i])).collect();
+        let (state, _, _) = batch_add(&params, generator, &elems);
+        let mut witnesses = create_all_mem_wit(&params, generator, &elems);
+
+        // Delete two of the elements and add three new ones.
+        let (del_state, deletions, _) = batch_delete(&params, state, &vec![(elems[1], witnesses[1]), (elems[6], witnesses[6])]).unwrap();
+        let new_elems: Vec<U2048> = (8..11u8).map(|i| subroutines::hash_to_prime(&params, &[i])).collect();
+        let (new_state, additions, _) = batch_add(&params, del_state, &new_elems);
+
+        // One witness is tampered with.
+        witnesses[3] = params.op(&witnesses[3], &U2048::from(2));
+
+        let results = batch_update_mem_wits(&params, &elems, &witnesses, new_state, &additions, &deletions).unwrap();
+        for i in 0..elems.len() {
+            assert_eq!(results[i], update_mem_wit(&params, elems[i], witnesses[i], new_state, &additions, &deletions));
+        }
+        assert_eq!(verify_mem_wit(&params, new_state, results[0].unwrap(), elems[0]), true);
+        assert_eq!(verify_mem_wit(&params, new_state, results[7].unwrap(), elems[7]), true);
+        assert_eq!(results[1], Err(Error::NotCoprime));
+        assert_eq!(results[3], Err(Error::InvalidWitness));
+
+        // A new state that does not follow from the additions and deletions.
+        let results = batch_update_mem_wits(&params, &elems, &witnesses, state, &additions, &deletions).unwrap();
+        assert_eq!(results.iter().all(|result| *result == Err(Error::InvalidWitness)), true);
+        assert_eq!(batch_update_mem_wits(&params, &[], &[], new_state, &additions, &deletions), Ok(vec![]));
+
+        // Every element needs exactly one witness.
+        assert_eq!(batch_update_mem_wits(&params, &elems, &witnesses[..7], new_state, &additions, &deletions), Err(Error::LengthMismatch));
+        assert_eq!(batch_update_mem_wits(&params, &elems[..7], &witnesses, new_state, &additions, &deletions), Err(Error::LengthMismatch));
+    }
+
+    #[test]
+    fn test_union() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), crate::LAMBDA).unwrap();
+        let generator = params.generator;
+        let shard_x = vec![U2048::from(3), U2048::from(5), U2048::from(7)];
+        let shard_y = vec![U2048::from(11), U2048::from(13)];
+        let (state_x, agg_x, _) = batch_add(&params, generator, &shard_x);
+        let (state_y, agg_y, _) = batch_add(&params, generator, &shard_y);
+
+        let (union, proof) = crate::union(&params, state_x, state_y, &agg_y).unwrap();
+        assert_eq!(union, params.exp(&generator, U2048::from(3 * 5 * 7 * 11 * 13)));
+        assert_eq!(crate::verify_union(&params, state_x, state_y, union, proof), true);
+        assert_eq!(crate::verify_union(&params, state_y, state_x, union, proof), false);
+        assert_eq!(crate::verify_union(&params, state_x, state_y, params.op(&union, &U2048::from(2)), proof), false);
+        assert_eq!(crate::union(&params, state_x, state_y, &agg_x), Err(Error::InvalidWitness));
+
+        // Witnesses from either shard are converted into witnesses for the union.
+        let witness_x = mem_wit_create(&params, generator, &agg_x, &big(5)).unwrap();
+        let witness_y = mem_wit_create(&params, generator, &agg_y, &big(13)).unwrap();
+        assert_eq!(verify_mem_wit(&params, union, union_mem_wit(&params, witness_x, &agg_y), U2048::from(5)), true);
+        assert_eq!(verify_mem_wit(&params, union, union_mem_wit(&params, witness_y, &agg_x), U2048::from(13)), true);
+    }
+
+    #[test]
+    fn test_mem_wit_custom_params() {
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(3), crate::LAMBDA).unwrap();
+        let elems = vec![U2048::from(3), U2048::from(5), U2048::from(7)];
+        let (state, agg, _) = batch_add(&params, params.generator, &elems);
+
+        let witness = mem_wit_create(&params, params.generator, &agg, &big(5)).unwrap();
+        assert_eq!(verify_mem_wit(&params, state, witness, U2048::from(5)), true);
+        assert_eq!(verify_mem_wit(&AccumulatorParams::default(), state, witness, U2048::from(5)), false);
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_class_group_witnesses() {
+        let group = ClassGroup::from_seed(b"test_class_group_witnesses", 128);
+        let elems = vec![U2048::from(3), U2048::from(5), U2048::from(7), U2048::from(11)];
+        let old_state = group.generator();
+        let (state, agg, proof) = batch_add(&group, old_state, &elems);
+        assert_eq!(proofs::verify_poe(&group, old_state, &agg, state, proof), true);
+
+        let witnesses = create_all_mem_wit(&group, old_state, &elems);
+        for (elem, witness) in elems.iter().zip(witnesses.iter()) {
+            assert_eq!(verify_mem_wit(&group, state, *witness, *elem), true);
+        }
+
+        let (aggregate, proof) = agg_mem_wit(&group, state, witnesses[0], witnesses[1], elems[0], elems[1]).unwrap();
+        assert_eq!(verify_agg_mem_wit(&group, state, &(BigUint::from(elems[0]) * elems[1]), aggregate, proof), true);
+
+        let deletions = vec![(elems[1], witnesses[1]), (elems[3], witnesses[3])];
+        let (new_state, _, _) = batch_delete(&group, state, &deletions).unwrap();
+        assert_eq!(new_state, group.exp(&old_state, elems[0] * elems[2]));
+        assert_eq!(batch_delete(&group, state, &vec![deletions[0], deletions[0]]), Err(Error::NotCoprime));
+        assert_eq!(batch_delete(&group, state, &vec![(elems[1], witnesses[0])]), Err(Error::InvalidWitness));
+        assert_eq!(batch_delete(&group, state, &vec![]), Err(Error::Empty));
+
+        let non_mem_wit = non_mem_wit_create(&group, old_state, &agg, U2048::from(13)).unwrap();
+        assert_eq!(verify_non_mem_wit(&group, old_state, state, non_mem_wit, U2048::from(13)), true);
+        assert_eq!(verify_non_mem_wit(&group, old_state, state, non_mem_wit, U2048::from(17)), false);
+
+        let non_mem_wit = update_non_mem_wit(&group, U2048::from(13), non_mem_wit, state, new_state, &big(1),
+                                             &(BigUint::from(elems[1]) * elems[3])).unwrap();
+        assert_eq!(verify_non_mem_wit(&group, old_state, new_state, non_mem_wit, U2048::from(13)), true);
+
+        let proof = agg_non_mem_wit(&group, old_state, state, &agg, &big(13 * 17)).unwrap();
+        assert_eq!(verify_agg_non_mem_wit(&group, old_state, state, proof, &big(13 * 17)), true);
+        assert_eq!(verify_agg_non_mem_wit(&group, old_state, state, proof, &big(13 * 19)), false);
+    }
+}
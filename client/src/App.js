@@ -99,7 +99,7 @@ function Main () {
           <Divider />
           <Grid.Row>
             <Mint accountPair={accountPair} wasm={wasm}/>
-            <Transaction accountPair={accountPair} />
+            <Transaction accountPair={accountPair} wasm={wasm} />
           </Grid.Row>
           <Divider />
           <Grid.Row>
@@ -12,18 +12,20 @@ export default function Mint (props) {
 
   const [UTXO, setUTXO] = useState({
     ID: '',
-    elem: ''
+    amount: '',
+    utxo: ''
   });
 
-  const { ID, elem } = UTXO;
+  const { ID, amount, utxo } = UTXO;
 
   const onChange = (_, data) =>
     setUTXO(UTXO => ({ ...UTXO, [data.name]: data.value }));
 
   function createUTXO () {
-    const utxo = wasm.get_utxo_elem(keyring.decodeAddress(accountPair.address, true), BigInt(ID));
-    const hash = BigInt(u8aToBn(wasm.hash_to_prime(new Uint8Array(utxo))));
-    setUTXO(UTXO => ({ ...UTXO, elem: hash }));
+    const pubKey = keyring.decodeAddress(accountPair.address, true);
+    const encoded = wasm.get_utxo_elem(pubKey, BigInt(ID), BigInt(amount));
+    const hash = BigInt(u8aToBn(wasm.hash_to_prime(new Uint8Array(encoded))));
+    setUTXO(UTXO => ({ ...UTXO, utxo: { pub_key: pubKey, id: ID, amount } }));
     alert('The value of the coin is: ' + hash);
   }
 
@@ -41,6 +43,16 @@ export default function Mint (props) {
             name='ID'
           />
         </Form.Field>
+        <Form.Field>
+          <Input
+            onChange={onChange}
+            label='Enter Amount'
+            fluid
+            id='input'
+            type='text'
+            name='amount'
+          />
+        </Form.Field>
         <Form.Field>
           <Button
             onClick={createUTXO}
@@ -57,7 +69,7 @@ export default function Mint (props) {
             setStatus={setStatus}
             type='TRANSACTION'
             attrs={{
-              params: [elem],
+              params: [utxo],
               tx: api.tx.stateless && api.tx.stateless.mint
             }}
           />
@@ -9,16 +9,17 @@ import { U8a } from '@polkadot/types/codec';
 export default function Transaction (props) {
   const { api } = useSubstrate();
   const [status, setStatus] = useState(null);
-  const { accountPair } = props;
+  const { accountPair, wasm } = props;
 
   const [formState, setFormState] = useState({
     ID: '',
+    amount: '',
     address: '',
     witness: '',
     transaction: ''
   });
 
-  const { ID, address, witness, transaction } = formState;
+  const { ID, amount, address, witness, transaction } = formState;
 
   const onChange = (_, data) =>
     setFormState(formState => ({ ...formState, [data.name]: data.value }));
@@ -32,14 +33,28 @@ export default function Transaction (props) {
     const receiver = keyring.decodeAddress(address, true);
     const idNum = BigInt(ID);
 
-    const input = { sender, idNum };
-    const output = { receiver, idNum };
+    const input = { pub_key: sender, id: idNum, amount };
+    const output = { pub_key: receiver, id: idNum, amount };
 
     const newWitness = new U8a(bnToU8a(BigInt(witness), 2048, true));
 
-    const signature = accountPair.sign(idNum);
+    // The runtime only checks that the spent UTXO hashes to a prime at this nonce.
+    const inputElem = wasm.get_utxo_elem(sender, idNum, BigInt(amount));
+    const nonce = wasm.get_prime_nonce(inputElem);
 
-    const tx = { input, output, newWitness, signature };
+    // The owner of the input signs the encoded input and output UTXOs.
+    const payload = wasm.get_signing_payload(
+      inputElem,
+      wasm.get_utxo_elem(receiver, idNum, BigInt(amount))
+    );
+    const signature = accountPair.sign(payload);
+
+    const tx = {
+      inputs: [{ utxo: input, witness: newWitness, nonce, signature: { Single: signature } }],
+      outputs: [output],
+      agg_witness: null,
+      bls_signature: null
+    };
     setFormState(formState => ({ ...formState, transaction: tx }));
     alert('Transaction created! Ready to submit to the blockchain.');
   }
@@ -58,6 +73,16 @@ export default function Transaction (props) {
             name='ID'
           />
         </Form.Field>
+        <Form.Field>
+          <Input
+            onChange={onChange}
+            label='Enter Coin Amount'
+            fluid
+            id='input'
+            type='text'
+            name='amount'
+          />
+        </Form.Field>
         <Form.Field>
           <Input
             onChange={onChange}
@@ -0,0 +1,134 @@
+/// BLS Signatures
+/// BLS signatures over BLS12-381 with public keys in G2(96 bytes compressed) and signatures in G1(48 bytes
+/// compressed). A signature is sk * H(m), where H hashes to G1 as specified in the IETF hash-to-curve draft.
+/// Signatures on different messages combine into a single point by addition, and the aggregate of n
+/// signatures is checked with n + 1 Miller loops and a single final exponentiation instead of n separate
+/// pairing equations. The aggregate is only sound if the messages are distinct. The runtime ensures this by
+/// having each input sign its own UTXO(see Transaction::bls_message), which rules out rogue key attacks
+/// without proofs of possession.
+
+use bls12_381::{multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, Gt, Scalar};
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use rstd::prelude::Vec;
+
+/// Domain separation tag of the basic scheme with signatures in G1.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+pub const PUBLIC_KEY_LEN: usize = 96;
+pub const SIGNATURE_LEN: usize = 48;
+
+/// Decodes a compressed public key. Rejects points outside of the prime order subgroup and the identity,
+/// which would verify any signature.
+pub fn decode_public_key(bytes: &[u8]) -> Option<G2Affine> {
+    if bytes.len() != PUBLIC_KEY_LEN {
+        return None;
+    }
+    let mut compressed = [0u8; PUBLIC_KEY_LEN];
+    compressed.copy_from_slice(bytes);
+    let key: Option<G2Affine> = G2Affine::from_compressed(&compressed).into();
+    return key.filter(|key| !bool::from(key.is_identity()));
+}
+
+/// Decodes a compressed signature. Rejects points outside of the prime order subgroup.
+pub fn decode_signature(bytes: &[u8]) -> Option<G1Affine> {
+    if bytes.len() != SIGNATURE_LEN {
+        return None;
+    }
+    let mut compressed = [0u8; SIGNATURE_LEN];
+    compressed.copy_from_slice(bytes);
+    return G1Affine::from_compressed(&compressed).into();
+}
+
+/// Hashes a message to a point in G1.
+pub fn hash_to_point(message: &[u8]) -> G1Affine {
+    let point = <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(message, DST);
+    return G1Affine::from(point);
+}
+
+/// Compressed public key of a secret key.
+pub fn public_key(secret: &Scalar) -> Vec<u8> {
+    return G2Affine::from(G2Affine::generator() * secret).to_compressed().to_vec();
+}
+
+/// Signs a message with a secret key.
+pub fn sign(secret: &Scalar, message: &[u8]) -> Vec<u8> {
+    return G1Affine::from(hash_to_point(message) * secret).to_compressed().to_vec();
+}
+
+/// Adds up a set of signatures. Returns None if any of them is invalid.
+pub fn aggregate(signatures: &[&[u8]]) -> Option<Vec<u8>> {
+    let mut sum = G1Projective::identity();
+    for signature in signatures {
+        sum += decode_signature(signature)?;
+    }
+    return Some(G1Affine::from(sum).to_compressed().to_vec());
+}
+
+/// Verifies an aggregate signature of a list of (public key, message) pairs by checking that
+/// e(signature, g2) = e(H(m_1), pk_1) * ... * e(H(m_n), pk_n). The messages must be distinct.
+pub fn verify_aggregate(signed: &[(Vec<u8>, Vec<u8>)], signature: &[u8]) -> bool {
+    let signature = match decode_signature(signature) {
+        Some(signature) => signature,
+        None => return false,
+    };
+    let mut points = Vec::with_capacity(signed.len() + 1);
+    let mut keys = Vec::with_capacity(signed.len() + 1);
+    for (public_key, message) in signed {
+        match decode_public_key(public_key) {
+            Some(key) => keys.push(G2Prepared::from(key)),
+            None => return false,
+        }
+        points.push(hash_to_point(message));
+    }
+    // Moves the signature to the right hand side so that the whole product must equal one.
+    points.push(-signature);
+    keys.push(G2Prepared::from(G2Affine::generator()));
+
+    let terms: Vec<(&G1Affine, &G2Prepared)> = points.iter().zip(keys.iter()).collect();
+    return multi_miller_loop(&terms).final_exponentiation() == Gt::identity();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        let secret = Scalar::from(12345u64);
+        let signature = sign(&secret, b"message");
+        let signed = vec![(public_key(&secret), b"message".to_vec())];
+        assert_eq!(verify_aggregate(&signed, &signature), true);
+        assert_eq!(verify_aggregate(&signed, &sign(&secret, b"other")), false);
+        assert_eq!(verify_aggregate(&[(public_key(&Scalar::from(1u64)), b"message".to_vec())], &signature), false);
+        assert_eq!(verify_aggregate(&signed, &signature[1..]), false);
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let secrets: Vec<Scalar> = (1..5u64).map(|i| Scalar::from(i * 1000 + 7)).collect();
+        let signed: Vec<(Vec<u8>, Vec<u8>)> = secrets.iter().enumerate()
+            .map(|(i, secret)| (public_key(secret), vec![i as u8; 10]))
+            .collect();
+        let signatures: Vec<Vec<u8>> = secrets.iter().zip(&signed).map(|(secret, (_, message))| sign(secret, message)).collect();
+        let refs: Vec<&[u8]> = signatures.iter().map(|signature| signature.as_slice()).collect();
+        let agg = aggregate(&refs).unwrap();
+        assert_eq!(verify_aggregate(&signed, &agg), true);
+
+        // Aggregation is associative, so the aggregate can be built one signature at a time.
+        let partial = aggregate(&refs[..2]).unwrap();
+        let rest = aggregate(&refs[2..]).unwrap();
+        assert_eq!(aggregate(&[&partial, &rest]), Some(agg.clone()));
+
+        // A missing signature or a message attributed to the wrong key invalidates the aggregate.
+        assert_eq!(verify_aggregate(&signed, &partial), false);
+        let mut swapped = signed.clone();
+        swapped[0].0 = signed[1].0.clone();
+        swapped[1].0 = signed[0].0.clone();
+        assert_eq!(verify_aggregate(&swapped, &agg), false);
+        assert_eq!(aggregate(&[&agg, &[0u8; 3]]), None);
+
+        // The identity is not a valid public key.
+        let identity = G2Affine::identity().to_compressed().to_vec();
+        assert_eq!(decode_public_key(&identity), None);
+    }
+}
@@ -61,6 +61,9 @@ pub type Hash = primitives::H256;
 /// Digest item type.
 pub type DigestItem = generic::DigestItem<Hash>;
 
+/// BLS signature aggregation for the stateless module in `./bls.rs`
+mod bls;
+
 /// Used for the module template in `./stateless.rs`
 mod stateless;
 
@@ -256,14 +259,27 @@ parameter_types! {
     pub const KeySpace: u8 = 255;
 }
 
+/// Accumulator parameters shared by the stateless modules. Swap in "AccumulatorParams::rsa_2048()"
+/// to run with a production sized modulus, or change the stateless module's "Group" to a class group
+/// to avoid a trusted setup.
+pub struct StatelessParams;
+impl support::traits::Get<accumulator::AccumulatorParams> for StatelessParams {
+    fn get() -> accumulator::AccumulatorParams {
+        accumulator::AccumulatorParams::default()
+    }
+}
+
 /// Used for the module template in `./stateless.rs`
 impl stateless::Trait for Runtime {
     type Event = Event;
+    type Group = accumulator::AccumulatorParams;
+    type Params = StatelessParams;
 }
 
 impl vector_commitment::Trait for Runtime {
     type Event = Event;
     type KeySpace = KeySpace;
+    type Params = StatelessParams;
 }
 
 construct_runtime!(
@@ -279,7 +295,7 @@ pub enum Runtime where
     Indices: indices::{default, Config<T>},
     Balances: balances,
     Sudo: sudo,
-    Stateless: stateless::{Module, Call, Storage, Event},
+    Stateless: stateless::{Module, Call, Storage, Event<T>},
     StatelessAccounts: vector_commitment::{Module, Call, Storage, Event<T>},
 
 }
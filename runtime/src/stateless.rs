@@ -14,45 +14,125 @@
 use support::{decl_module, decl_storage, decl_event, ensure, StorageValue, dispatch::Result, traits::Get};
 use system::ensure_signed;
 use primitive_types::H256;
+use primitives::sr25519;
+use sr_primitives::{AnySignature, traits::Verify};
+use runtime_io::blake2_256;
 use rstd::prelude::Vec;
 use rstd::vec;
 use codec::{Encode, Decode};
 use accumulator::*;
+use crate::bls;
 
-/// At the moment, this particular struct resembles more closely an NFT.
+/// An unspent output that holds "amount" coins. The amount is part of the encoding and is therefore bound
+/// to the prime representation of the UTXO. "pub_key" is an sr25519 or ed25519 public key of the owner, or the
+/// blake2 hash of a BLS public key(see Signature::Bls).
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Default, Clone, Encode, Decode, PartialEq, Eq, Copy)]
 pub struct UTXO {
     pub_key: H256,
     id: u64,
+    amount: u64,
 }
 
-/// Primitive transaction model with one input and one output.
+/// Maximum number of inputs and outputs of a single transaction. The product of the inputs of a transaction
+/// must fit in a U2048(see Transaction::agg_witness), which holds 8 primes of up to 256 bits.
+pub const MAX_INPUTS: usize = 8;
+pub const MAX_OUTPUTS: usize = 8;
+
+/// A spent UTXO.
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Default, Clone, Encode, Decode, PartialEq, Eq)]
-pub struct Transaction {
-    input: UTXO,
-    output: UTXO,
+pub struct Input {
+    utxo: UTXO,
+    /// Membership witness of the UTXO. May be left empty if the transaction carries an aggregated witness.
     witness: Vec<u8>,
-    // Would in practice include a signature here.
+    /// Nonce at which the UTXO hashes to a prime(see subroutines::hash_to_prime_with_nonce).
+    nonce: u64,
+    /// Proof that the owner of the UTXO authorized the transaction.
+    signature: Signature,
+}
+
+/// Authorization of an input by its owner.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Encode, Decode, PartialEq, Eq)]
+pub enum Signature {
+    /// sr25519 or ed25519 signature over Transaction::signing_payload. Verified when the transaction is added.
+    Single(AnySignature),
+    /// Compressed BLS public key of the owner. The signature over Transaction::bls_message is part of
+    /// Transaction::bls_signature and is verified together with the other BLS inputs of the transaction.
+    Bls(Vec<u8>),
+}
+
+impl Default for Signature {
+    fn default() -> Self {
+        return Signature::Single(AnySignature::default());
+    }
+}
+
+/// Transaction model with multiple inputs and outputs.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Default, Clone, Encode, Decode, PartialEq, Eq)]
+pub struct Transaction {
+    inputs: Vec<Input>,
+    outputs: Vec<UTXO>,
+    /// Optional witness for the product of all inputs together with a proof of exponentiation(see
+    /// witnesses::agg_mem_wits). Replaces the witnesses of the individual inputs.
+    agg_witness: Option<(Vec<u8>, Vec<u8>)>,
+    /// Aggregate BLS signature of the inputs with a BLS owner(see bls::aggregate). Must be set if and only if
+    /// at least one input has a BLS owner.
+    bls_signature: Option<Vec<u8>>,
+}
+
+impl Transaction {
+    /// Message signed by the owners of the inputs: the SCALE-encoded spent UTXOs followed by the outputs.
+    /// Witnesses and nonces are excluded so that witnesses can be updated without new signatures. Since a
+    /// UTXO can only be spent once, a signature cannot be replayed.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let inputs: Vec<UTXO> = self.inputs.iter().map(|input| input.utxo).collect();
+        let mut payload = inputs.encode();
+        self.outputs.encode_to(&mut payload);
+        return payload;
+    }
+
+    /// Message signed by the BLS owner of an input: the signing payload followed by the spent UTXO. Since a
+    /// UTXO is only spent once per block, the messages of a block are distinct and their signatures can be
+    /// aggregated safely.
+    pub fn bls_message(&self, utxo: &UTXO) -> Vec<u8> {
+        let mut message = self.signing_payload();
+        utxo.encode_to(&mut message);
+        return message;
+    }
 }
 
 pub trait Trait: system::Trait {
-    type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Hidden-order group that the accumulator operates in(Ex: AccumulatorParams for an RSA group).
+    type Group: Group;
+    /// Parameters of the group(modulus, generator and security parameter for an RSA group).
+    type Params: Get<Self::Group>;
 }
 
+/// Element of the group configured for the runtime.
+pub type ElemOf<T> = <<T as Trait>::Group as Group>::Elem;
+
 decl_storage! {
     trait Store for Module<T: Trait> as Stateless {
-        State get(get_state): U2048 = U2048::from(2);  // Use 2 as an arbitrary generator with "unknown" order.
-        SpentCoins get(get_spent_coins): Vec<(U2048, U2048)>;
-        NewCoins get(get_new_coins): Vec<U2048>
+        State: Option<ElemOf<T>>;  // Unset until the first update. See "get_state".
+        SpentCoins get(get_spent_coins): Vec<(U2048, ElemOf<T>)>;
+        NewCoins get(get_new_coins): Vec<U2048>;
+        Fees get(get_fees): u64;
     }
 }
 
 decl_event!(
-    pub enum Event {
-        Deletion(U2048, U2048, U2048),
-        Addition(U2048, U2048, U2048),
+    pub enum Event<T> where Elem = ElemOf<T> {
+        Deletion(Elem, BigUint, Elem),
+        Addition(Elem, BigUint, Elem),
+        /// State before the outputs of the block were added and the added elements. Recipients derive
+        /// their witnesses with witnesses::mem_wit_create from these.
+        Outputs(Elem, Vec<U2048>),
+        /// Sum of the fees of the transactions in the block. Fees are currently burned.
+        Fees(u64),
     }
 );
 
@@ -64,69 +144,183 @@ decl_module! {
 
         /// Receive request to execute a transaction.
         /// Verify the contents of a transaction and temporarily add it to a queue of verified transactions.
-        /// NOTE: Only works if one transaction per user per block is submitted.
+        /// The inputs are queued for deletion and the outputs for addition at the end of the block. Each
+        /// input and output may only appear once per block. The BLS inputs of a transaction share one aggregate
+        /// signature that is checked here with a single multi-pairing. The saving is limited to the transaction:
+        /// signatures are not aggregated across the block, since one invalid signature would then void every
+        /// transaction of the block.
         pub fn addTransaction(origin, transaction: Transaction) -> Result {
             ensure_signed(origin)?;
-            // Arbitrarily cap the number of pending transactions to 100
-            ensure!(SpentCoins::get().len() < 100, "Transaction queue full. Please try again next block.");
-            // Also verify that the user is not spending to themselves
-            ensure!(transaction.input.pub_key != transaction.output.pub_key, "Cannot send coin to yourself.");
+            // Arbitrarily cap the number of pending inputs to 100
+            ensure!(Self::get_spent_coins().len() < 100, "Transaction queue full. Please try again next block.");
+            ensure!(!transaction.inputs.is_empty() && transaction.inputs.len() <= MAX_INPUTS, "Invalid number of inputs.");
+            ensure!(!transaction.outputs.is_empty() && transaction.outputs.len() <= MAX_OUTPUTS, "Invalid number of outputs.");
+
+            // The outputs may not exceed the inputs. The difference is the fee of the transaction.
+            let input_amount = transaction.inputs.iter()
+                .try_fold(0u64, |sum, input| sum.checked_add(input.utxo.amount))
+                .ok_or("Amount overflow.")?;
+            let output_amount = transaction.outputs.iter()
+                .try_fold(0u64, |sum, output| sum.checked_add(output.amount))
+                .ok_or("Amount overflow.")?;
+            let fee = input_amount.checked_sub(output_amount).ok_or("Outputs exceed inputs.")?;
+
+            // Only the owners of the inputs can spend them.
+            let payload = transaction.signing_payload();
+            let mut bls_signed = Vec::new();
+            for input in &transaction.inputs {
+                match &input.signature {
+                    Signature::Single(signature) => {
+                        let owner = sr25519::Public::from_raw(input.utxo.pub_key.to_fixed_bytes());
+                        ensure!(signature.verify(payload.as_slice(), &owner), "Invalid signature.");
+                    },
+                    Signature::Bls(public_key) => {
+                        ensure!(H256::from(blake2_256(public_key)) == input.utxo.pub_key
+                                && bls::decode_public_key(public_key).is_some(), "Invalid signature.");
+                        bls_signed.push((public_key.clone(), transaction.bls_message(&input.utxo)));
+                    },
+                }
+            }
+            match (&transaction.bls_signature, bls_signed.is_empty()) {
+                (None, true) => {},
+                (Some(signature), false) => {
+                    ensure!(bls::verify_aggregate(&bls_signed, signature), "Invalid signature.");
+                },
+                _ => return Err("Invalid signature."),
+            }
+
+            let params = T::Params::get();
+            let state = Self::get_state();
+            let spent_coins = Self::get_spent_coins();
+            let mut spent_elems = Vec::with_capacity(transaction.inputs.len());
+            for input in &transaction.inputs {
+                let spent_elem = subroutines::verify_prime_nonce(&params, &input.utxo.encode(), input.nonce)?;
+                // Witnesses are checked against the state at the start of the block, so a second spend of
+                // the same input would pass the checks below. Queued entries may be products of the inputs
+                // of a transaction.
+                ensure!(!spent_elems.contains(&spent_elem)
+                        && !spent_coins.iter().any(|(elems, _)| (*elems % spent_elem).is_zero()), "Input already spent in this block.");
+                spent_elems.push(spent_elem);
+            }
 
-            // Verify witness
-            let spent_elem = subroutines::hash_to_prime(&transaction.input.encode());
-            let witness = U2048::from_little_endian(&transaction.witness);
-            ensure!(witnesses::verify_mem_wit(State::get(), witness, spent_elem), "Witness is invalid");
+            // Verify witnesses
+            let mut spent = Vec::with_capacity(spent_elems.len());
+            match &transaction.agg_witness {
+                Some((witness, proof)) => {
+                    let witness = params.decode_elem(witness)?;
+                    let proof = params.decode_elem(proof)?;
+                    let product = subroutines::prime_product(&spent_elems);
+                    ensure!(witnesses::verify_agg_mem_wit(&params, state, &product, witness, proof), "Witness is invalid");
+                    // Deleted as a single composite element by batch_delete.
+                    spent.push((product.to_u2048()?, witness));
+                },
+                None => {
+                    // Each witness is checked exactly. A batch check would accept a witness that is off by an
+                    // element of order two(see witnesses::verify_mem_wits_batch), which batch_delete then rejects
+                    // on finalization together with every other transaction of the block.
+                    for (input, spent_elem) in transaction.inputs.iter().zip(spent_elems) {
+                        let witness = params.decode_elem(&input.witness)?;
+                        ensure!(witnesses::verify_mem_wit(&params, state, witness, spent_elem), "Witness is invalid");
+                        spent.push((spent_elem, witness));
+                    }
+                },
+            }
 
-            let new_elem = subroutines::hash_to_prime(&transaction.output.encode());
+            let new_coins = Self::get_new_coins();
+            let mut new_elems = Vec::with_capacity(transaction.outputs.len());
+            for output in &transaction.outputs {
+                let new_elem = subroutines::hash_to_prime(&params, &output.encode());
+                ensure!(!new_elems.contains(&new_elem) && !new_coins.contains(&new_elem), "Output already created in this block.");
+                new_elems.push(new_elem);
+            }
 
             // Update storage items.
-            SpentCoins::append(&vec![(spent_elem, witness)]);
+            <SpentCoins<T>>::append(&spent);
+            NewCoins::append(&new_elems);
+            Fees::mutate(|fees| *fees = fees.saturating_add(fee));
 
             Ok(())
         }
 
-        /// Arbitrary replacement for Proof-of-Work to create new coins.
-        pub fn mint(origin, elem: u64) -> Result {
+        /// Arbitrary replacement for Proof-of-Work to create new coins. The coin is added together with the
+        /// outputs of the block so that the witnesses of pending transactions stay valid.
+        pub fn mint(origin, utxo: UTXO) -> Result {
             ensure_signed(origin)?;
-            let state = subroutines::mod_exp(Self::get_state(), U2048::from(elem), U2048::from_dec_str(MODULUS).unwrap());
-            State::put(state);
+            let new_elem = subroutines::hash_to_prime(&T::Params::get(), &utxo.encode());
+            ensure!(!Self::get_new_coins().contains(&new_elem), "Output already created in this block.");
+            NewCoins::append(&vec![new_elem]);
             Ok(())
         }
 
         /// Batch delete spent coins and add new coins on block finalization
         fn on_finalize() {
-            // Clause here to protect against empty blocks
-            if Self::get_spent_coins().len() > 0 {
-                // Delete spent coins from aggregator and distribute proof
-                let (state, agg, proof) = accumulator::batch_delete(State::get(), &Self::get_spent_coins());
-                Self::deposit_event(Event::Deletion(state, agg, proof));
-
-                // Add new coins to aggregator and distribute proof
-                let (state, agg, proof) = accumulator::batch_add(state, &Self::get_new_coins());
-                Self::deposit_event(Event::Addition(state, agg, proof));
-
-                // Update state
-                State::put(state);
+            // Clause here to protect against empty blocks. If the batch is invalid, the accumulator is left
+            // unchanged and no fees are reported.
+            let changed = Self::get_spent_coins().len() > 0 || Self::get_new_coins().len() > 0;
+            let applied = !changed || Self::update_state().is_ok();
+            if applied && Self::get_fees() > 0 {
+                Self::deposit_event(RawEvent::Fees(Self::get_fees()));
             }
 
             // Clear storage
-            SpentCoins::kill();
+            <SpentCoins<T>>::kill();
             NewCoins::kill();
+            Fees::kill();
         }
     }
 }
 
+impl<T: Trait> Module<T> {
+    /// Returns the current state of the accumulator. Before any coins are added, this is the
+    /// generator of the group.
+    pub fn get_state() -> ElemOf<T> {
+        <State<T>>::get().unwrap_or(T::Params::get().generator())
+    }
+
+    /// Batch deletes the spent coins and batch adds the new coins. The state is only updated and the
+    /// events are only emitted if both batches succeed. Blocks that only mint coins skip the deletion.
+    fn update_state() -> rstd::result::Result<(), Error> {
+        let params = T::Params::get();
+
+        // Delete spent coins from aggregator
+        let spent_coins = Self::get_spent_coins();
+        let mut del_state = Self::get_state();
+        let mut deletion = None;
+        if !spent_coins.is_empty() {
+            let (state, del_agg, del_proof) = accumulator::batch_delete(&params, del_state, &spent_coins)?;
+            del_state = state;
+            deletion = Some((del_agg, del_proof));
+        }
+
+        // Add new coins to aggregator
+        let new_coins = Self::get_new_coins();
+        let (state, agg, proof) = accumulator::batch_add(&params, del_state, &new_coins);
+
+        // Distribute proofs and the data that recipients need for their witnesses
+        if let Some((del_agg, del_proof)) = deletion {
+            Self::deposit_event(RawEvent::Deletion(del_state, del_agg, del_proof));
+        }
+        Self::deposit_event(RawEvent::Addition(state, agg, proof));
+        Self::deposit_event(RawEvent::Outputs(del_state, new_coins));
+
+        // Update state
+        <State<T>>::put(state);
+        return Ok(());
+    }
+}
+
 /// tests for this module
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use runtime_io::with_externalities;
-    use primitives::{H256, Blake2Hasher};
+    use primitives::{H256, Blake2Hasher, Pair, ed25519};
     use support::{impl_outer_origin, parameter_types};
     use sr_primitives::{traits::{BlakeTwo256, IdentityLookup, OnFinalize}, testing::Header};
     use sr_primitives::weights::Weight;
     use sr_primitives::Perbill;
+    use bls12_381::Scalar;
 
     impl_outer_origin! {
 	    pub enum Origin for Test {}
@@ -144,6 +338,13 @@ mod tests {
         pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
     }
 
+    pub struct TestParams;
+    impl Get<AccumulatorParams> for TestParams {
+        fn get() -> AccumulatorParams {
+            AccumulatorParams::default()
+        }
+    }
+
     impl system::Trait for Test {
         type Origin = Origin;
         type Call = ();
@@ -165,6 +366,8 @@ mod tests {
 
     impl Trait for Test {
         type Event = ();
+        type Group = AccumulatorParams;
+        type Params = TestParams;
     }
 
     type Stateless = Module<Test>;
@@ -179,8 +382,9 @@ mod tests {
     #[test]
     fn test_add() {
         with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
             let elems = vec![U2048::from(3), U2048::from(5), U2048::from(7)];
-            let (state, _, _) = accumulator::batch_add(Stateless::get_state(), &elems);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems);
             assert_eq!(state, U2048::from(5));
         });
     }
@@ -188,17 +392,18 @@ mod tests {
     #[test]
     fn test_del() {
         with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
             let elems = vec![U2048::from(3), U2048::from(5), U2048::from(7)];
             // Collect witnesses for the added elements
-            let witnesses = witnesses::create_all_mem_wit(Stateless::get_state(), &elems);
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems);
 
             // Add elements
-            let (state, _, _) = accumulator::batch_add(Stateless::get_state(), &elems);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems);
             assert_eq!(state, U2048::from(5));
 
             // Delete elements
             let deletions = vec![(elems[0], witnesses[0]), (elems[1], witnesses[1]), (elems[2], witnesses[2])];
-            let (state, _, _) = accumulator::batch_delete(Stateless::get_state(), &deletions);
+            let (state, _, _) = accumulator::batch_delete(&params, Stateless::get_state(), &deletions).unwrap();
             assert_eq!(state, U2048::from(2));
         });
     }
@@ -206,100 +411,469 @@ mod tests {
     #[test]
     fn test_block() {
         with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
             // 1. Construct UTXOs.
             let utxo_0 = UTXO {
-                pub_key: H256::from_low_u64_be(0),
+                pub_key: owner(0),
                 id: 0,
+                amount: 10,
             };
 
             let utxo_1 = UTXO {
-                pub_key: H256::from_low_u64_be(1),
+                pub_key: owner(1),
                 id: 1,
+                amount: 10,
             };
 
             let utxo_2 = UTXO {
-                pub_key: H256::from_low_u64_be(2),
+                pub_key: owner(2),
                 id: 2,
+                amount: 10,
             };
 
             // 2. Hash each UTXO to a prime.
-            let elem_0 = subroutines::hash_to_prime(&utxo_0.encode());
-            let elem_1 = subroutines::hash_to_prime(&utxo_1.encode());
-            let elem_2 = subroutines::hash_to_prime(&utxo_2.encode());
+            let (elem_0, nonce_0) = subroutines::hash_to_prime_with_nonce(&params, &utxo_0.encode());
+            let (elem_1, nonce_1) = subroutines::hash_to_prime_with_nonce(&params, &utxo_1.encode());
+            let (elem_2, nonce_2) = subroutines::hash_to_prime_with_nonce(&params, &utxo_2.encode());
             let elems = vec![elem_0, elem_1, elem_2];
 
             // 3. Produce witnesses for the added elements.
-            let witnesses = witnesses::create_all_mem_wit(Stateless::get_state(), &elems);
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems);
 
             // 4. Add elements to the accumulator.
-            let (state, _, _) = accumulator::batch_add(Stateless::get_state(), &elems);
-            State::put(state);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems);
+            <State<Test>>::put(state);
 
             // 5. Construct new UTXOs and derive integer representations.
             let utxo_3 = UTXO {
-                pub_key: H256::from_low_u64_be(1),
+                pub_key: owner(1),
                 id: 0,
+                amount: 10,
             };
 
             let utxo_4 = UTXO {
-                pub_key: H256::from_low_u64_be(2),
+                pub_key: owner(2),
                 id: 1,
+                amount: 10,
             };
 
             let utxo_5 = UTXO {
-                pub_key: H256::from_low_u64_be(0),
+                pub_key: owner(0),
                 id: 2,
+                amount: 10,
             };
 
-            let elem_3 = subroutines::hash_to_prime(&utxo_3.encode());
-            let elem_4 = subroutines::hash_to_prime(&utxo_4.encode());
-            let elem_5 = subroutines::hash_to_prime(&utxo_5.encode());
+            let elem_3 = subroutines::hash_to_prime(&params, &utxo_3.encode());
+            let elem_4 = subroutines::hash_to_prime(&params, &utxo_4.encode());
+            let elem_5 = subroutines::hash_to_prime(&params, &utxo_5.encode());
 
             // 6. Construct transactions.
             let mut witness_0: [u8; 256] = [0; 256];
             witnesses[0].to_little_endian(&mut witness_0);
-            let tx_0 = Transaction {
-                input: utxo_0,
-                output: utxo_3,
-                witness: witness_0.to_vec(),
-            };
+            let tx_0 = sign(Transaction {
+                inputs: vec![Input {
+                    utxo: utxo_0,
+                    witness: witness_0.to_vec(),
+                    nonce: nonce_0,
+                    signature: Signature::default(),
+                }],
+                outputs: vec![utxo_3],
+                agg_witness: None,
+                bls_signature: None,
+            });
 
             let mut witness_1: [u8; 256] = [0; 256];
             witnesses[1].to_little_endian(&mut witness_1);
-            let tx_1 = Transaction {
-                input: utxo_1,
-                output: utxo_4,
-                witness: witness_1.to_vec(),
-            };
+            let tx_1 = sign(Transaction {
+                inputs: vec![Input {
+                    utxo: utxo_1,
+                    witness: witness_1.to_vec(),
+                    nonce: nonce_1,
+                    signature: Signature::default(),
+                }],
+                outputs: vec![utxo_4],
+                agg_witness: None,
+                bls_signature: None,
+            });
 
             let mut witness_2: [u8; 256] = [0; 256];
             witnesses[2].to_little_endian(&mut witness_2);
-            let tx_2 = Transaction {
-                input: utxo_2,
-                output: utxo_5,
-                witness: witness_2.to_vec(),
-            };
+            let tx_2 = sign(Transaction {
+                inputs: vec![Input {
+                    utxo: utxo_2,
+                    witness: witness_2.to_vec(),
+                    nonce: nonce_2,
+                    signature: Signature::default(),
+                }],
+                outputs: vec![utxo_5],
+                agg_witness: None,
+                bls_signature: None,
+            });
 
             // 7. Verify transactions. Note that this logic will eventually be executed automatically
             // by the block builder API eventually.
-            Stateless::addTransaction(Origin::signed(1), tx_0);
-            Stateless::addTransaction(Origin::signed(1), tx_1);
-            Stateless::addTransaction(Origin::signed(1), tx_2);
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx_0), Ok(()));
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx_1), Ok(()));
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx_2), Ok(()));
+            assert_eq!(Stateless::get_new_coins(), vec![elem_3, elem_4, elem_5]);
 
             // 8. Finalize the block.
             Stateless::on_finalize(System::block_number());
 
             assert_eq!(Stateless::get_state(),
-                       subroutines::mod_exp(U2048::from(2), elem_3 * elem_4 * elem_5, U2048::from_dec_str(MODULUS).unwrap()));
+                       subroutines::mod_exp(U2048::from(2), elem_3 * elem_4 * elem_5, params.modulus));
+            assert_eq!(Stateless::get_new_coins().len(), 0);
 
         });
     }
 
+    /// Key pair of the i-th test account.
+    fn pair(i: u8) -> sr25519::Pair {
+        return sr25519::Pair::from_seed_slice(&[i; 32]).unwrap();
+    }
+
+    /// Public key of the i-th test account.
+    fn owner(i: u8) -> H256 {
+        return H256::from(pair(i).public().0);
+    }
+
+    /// Signs every input of a transaction with the key of the test account that owns it.
+    fn sign(mut tx: Transaction) -> Transaction {
+        let payload = tx.signing_payload();
+        for input in &mut tx.inputs {
+            let key = (0..=255).map(pair).find(|key| H256::from(key.public().0) == input.utxo.pub_key).unwrap();
+            input.signature = Signature::Single(key.sign(&payload).into());
+        }
+        return tx;
+    }
+
+    /// Builds a signed transaction that spends "input" with the given witness.
+    fn spend(input: UTXO, output: UTXO, witness: U2048) -> Transaction {
+        return sign(Transaction { inputs: vec![to_input(input, witness)], outputs: vec![output], agg_witness: None, bls_signature: None });
+    }
+
+    /// Unsigned input that spends "utxo" with the given witness.
+    fn to_input(utxo: UTXO, witness: U2048) -> Input {
+        let (_, nonce) = subroutines::hash_to_prime_with_nonce(&TestParams::get(), &utxo.encode());
+        return Input { utxo, witness: witness.encode(), nonce, signature: Signature::default() };
+    }
+
+    #[test]
+    fn test_double_spend() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
+            let utxos: Vec<UTXO> = (0..4).map(|i| UTXO { pub_key: owner(i as u8), id: i, amount: 10 }).collect();
+            let elems = vec![subroutines::hash_to_prime(&params, &utxos[0].encode()),
+                             subroutines::hash_to_prime(&params, &utxos[1].encode())];
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems);
+            <State<Test>>::put(state);
+
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[0], utxos[2], witnesses[0])), Ok(()));
+            // The same input cannot be spent twice within a block.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[0], utxos[3], witnesses[0])),
+                       Err("Input already spent in this block."));
+            // Two transactions cannot create the same output.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[1], utxos[2], witnesses[1])),
+                       Err("Output already created in this block."));
+            assert_eq!(Stateless::get_spent_coins(), vec![(elems[0], witnesses[0])]);
+
+            Stateless::on_finalize(System::block_number());
+            let elem_2 = subroutines::hash_to_prime(&params, &utxos[2].encode());
+            assert_eq!(Stateless::get_state(), subroutines::mod_exp(U2048::from(2), elems[1] * elem_2, params.modulus));
+        });
+    }
+
+    #[test]
+    fn test_negated_witness() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
+            let utxos: Vec<UTXO> = (0..4).map(|i| UTXO { pub_key: owner(i as u8), id: i, amount: 10 }).collect();
+            let elems: Vec<U2048> = utxos[..2].iter().map(|utxo| subroutines::hash_to_prime(&params, &utxo.encode())).collect();
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems);
+            <State<Test>>::put(state);
+
+            // N - w differs from the witness by the element of order two and must not reach batch_delete.
+            let tx = sign(Transaction {
+                inputs: vec![to_input(utxos[0], witnesses[0]), to_input(utxos[1], params.modulus - witnesses[1])],
+                outputs: vec![UTXO { amount: 20, ..utxos[2] }],
+                agg_witness: None,
+                bls_signature: None,
+            });
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx), Err("Witness is invalid"));
+            assert_eq!(Stateless::get_spent_coins().len(), 0);
+
+            // The rest of the block is applied.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[0], utxos[3], witnesses[0])), Ok(()));
+            Stateless::on_finalize(System::block_number());
+            let elem_3 = subroutines::hash_to_prime(&params, &utxos[3].encode());
+            assert_eq!(Stateless::get_state(), subroutines::mod_exp(U2048::from(2), elems[1] * elem_3, params.modulus));
+        });
+    }
+
+    #[test]
+    fn test_multi_block() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
+            let utxos: Vec<UTXO> = (0..5).map(|i| UTXO { pub_key: owner(i as u8), id: i, amount: 10 }).collect();
+            let elems: Vec<U2048> = utxos.iter().map(|utxo| subroutines::hash_to_prime(&params, &utxo.encode())).collect();
+
+            // Block 1: spend the two initial coins into two new outputs.
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems[..2]);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems[..2].to_vec());
+            <State<Test>>::put(state);
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[0], utxos[2], witnesses[0])), Ok(()));
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[1], utxos[3], witnesses[1])), Ok(()));
+            Stateless::on_finalize(System::block_number());
+
+            // The recipient of utxo 2 derives its witness from the data of the Outputs event.
+            let (old_state, _, _) = accumulator::batch_delete(&params, state, &vec![(elems[0], witnesses[0]), (elems[1], witnesses[1])]).unwrap();
+            let agg = subroutines::prime_product(&elems[2..4]);
+            let witness = witnesses::mem_wit_create(&params, old_state, &agg, &BigUint::from(elems[2])).unwrap();
+            assert_eq!(witnesses::verify_mem_wit(&params, Stateless::get_state(), witness, elems[2]), true);
+
+            // Block 2: spend the output created in the previous block.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[2], utxos[4], witness)), Ok(()));
+            Stateless::on_finalize(System::block_number());
+            assert_eq!(Stateless::get_state(), subroutines::mod_exp(U2048::from(2), elems[3] * elems[4], params.modulus));
+        });
+    }
+
+    #[test]
+    fn test_multi_input() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
+            let mut utxos: Vec<UTXO> = (0..7).map(|i| UTXO { pub_key: owner(i as u8), id: i, amount: 10 }).collect();
+            // The two inputs of 10 pay for three outputs of 5 and a fee of 5.
+            for utxo in &mut utxos[3..6] {
+                utxo.amount = 5;
+            }
+            let elems: Vec<U2048> = utxos.iter().map(|utxo| subroutines::hash_to_prime(&params, &utxo.encode())).collect();
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems[..3]);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems[..3].to_vec());
+            <State<Test>>::put(state);
+
+            // Two inputs proven by one aggregated witness and three outputs.
+            let (agg_witness, proof) = witnesses::agg_mem_wits(&params, state, &[(elems[0], witnesses[0]), (elems[1], witnesses[1])]).unwrap();
+            let tx = sign(Transaction {
+                inputs: vec![to_input(utxos[0], witnesses[0]), to_input(utxos[1], witnesses[1])],
+                outputs: vec![utxos[3], utxos[4], utxos[5]],
+                agg_witness: Some((agg_witness.encode(), proof.encode())),
+                bls_signature: None,
+            });
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx), Ok(()));
+            assert_eq!(Stateless::get_spent_coins(), vec![(elems[0] * elems[1], agg_witness)]);
+            assert_eq!(Stateless::get_new_coins(), elems[3..6].to_vec());
+            assert_eq!(Stateless::get_fees(), 5);
+
+            // Inputs of an aggregated transaction cannot be spent again.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[1], utxos[6], witnesses[1])),
+                       Err("Input already spent in this block."));
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[2], utxos[6], witnesses[2])), Ok(()));
+
+            Stateless::on_finalize(System::block_number());
+            assert_eq!(Stateless::get_state(),
+                       subroutines::mod_exp(U2048::from(2), elems[3] * elems[4] * elems[5] * elems[6], params.modulus));
+            assert_eq!(Stateless::get_fees(), 0);
+        });
+    }
+
+    #[test]
+    fn test_transaction_limits() {
+        with_externalities(&mut new_test_ext(), || {
+            let utxo = UTXO { pub_key: owner(0), id: 0, amount: 10 };
+            let output = UTXO { pub_key: owner(1), id: 0, amount: 10 };
+            let mut tx = spend(utxo, output, Stateless::get_state());
+            tx.inputs = vec![tx.inputs[0].clone(); MAX_INPUTS + 1];
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx.clone()), Err("Invalid number of inputs."));
+            tx.inputs = Vec::new();
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx.clone()), Err("Invalid number of inputs."));
+            tx.inputs = vec![to_input(utxo, Stateless::get_state())];
+            tx.outputs = Vec::new();
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx.clone()), Err("Invalid number of outputs."));
+            tx.outputs = vec![output, output];
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx.clone()), Err("Outputs exceed inputs."));
+            tx.outputs = vec![UTXO { amount: u64::max_value(), ..output }, output];
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx), Err("Amount overflow."));
+        });
+    }
+
+    #[test]
+    fn test_signatures() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
+            let ed_pair = ed25519::Pair::from_seed_slice(&[7; 32]).unwrap();
+            let utxo_0 = UTXO { pub_key: owner(0), id: 0, amount: 10 };
+            let utxo_1 = UTXO { pub_key: H256::from(ed_pair.public().0), id: 1, amount: 10 };
+            let output = UTXO { pub_key: owner(2), id: 2, amount: 20 };
+            let elems = vec![subroutines::hash_to_prime(&params, &utxo_0.encode()),
+                             subroutines::hash_to_prime(&params, &utxo_1.encode())];
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems);
+            <State<Test>>::put(state);
+
+            // A witness alone is not enough to spend a coin.
+            let mut tx = Transaction {
+                inputs: vec![to_input(utxo_0, witnesses[0]), to_input(utxo_1, witnesses[1])],
+                outputs: vec![output],
+                agg_witness: None,
+                bls_signature: None,
+            };
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx.clone()), Err("Invalid signature."));
+
+            // Signed by someone other than the owner.
+            let payload = tx.signing_payload();
+            tx.inputs[0].signature = Signature::Single(pair(1).sign(&payload).into());
+            tx.inputs[1].signature = Signature::Single(ed_pair.sign(&payload).into());
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx.clone()), Err("Invalid signature."));
+
+            // The signatures bind the outputs.
+            tx.inputs[0].signature = Signature::Single(pair(0).sign(&payload).into());
+            let mut redirected = tx.clone();
+            redirected.outputs[0].pub_key = owner(3);
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), redirected), Err("Invalid signature."));
+
+            // sr25519 and ed25519 owners can spend together.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx), Ok(()));
+        });
+    }
+
+    /// Owner of the UTXOs of a BLS secret key.
+    fn bls_owner(secret: &Scalar) -> H256 {
+        return H256::from(blake2_256(&bls::public_key(secret)));
+    }
+
+    /// Transaction that spends "input" with the given witness. Signed with bls_sign.
+    fn bls_spend(input: UTXO, output: UTXO, witness: U2048) -> Transaction {
+        return Transaction { inputs: vec![to_input(input, witness)], outputs: vec![output], agg_witness: None, bls_signature: None };
+    }
+
+    /// Sets the BLS key of every input and signs the transaction with the aggregate of their signatures.
+    fn bls_sign(mut tx: Transaction, secrets: &[Scalar]) -> Transaction {
+        for (input, secret) in tx.inputs.iter_mut().zip(secrets) {
+            input.signature = Signature::Bls(bls::public_key(secret));
+        }
+        let signatures: Vec<Vec<u8>> = tx.inputs.iter().zip(secrets)
+            .map(|(input, secret)| bls::sign(secret, &tx.bls_message(&input.utxo)))
+            .collect();
+        let refs: Vec<&[u8]> = signatures.iter().map(|signature| signature.as_slice()).collect();
+        tx.bls_signature = bls::aggregate(&refs);
+        return tx;
+    }
+
+    #[test]
+    fn test_bls() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
+            let secrets: Vec<Scalar> = (1..4u64).map(|i| Scalar::from(i * 7919)).collect();
+            let mut utxos: Vec<UTXO> = (0..3).map(|i| UTXO { pub_key: bls_owner(&secrets[i]), id: i as u64, amount: 10 }).collect();
+            utxos.push(UTXO { pub_key: owner(0), id: 3, amount: 10 });
+            let outputs: Vec<UTXO> = (4..8).map(|i| UTXO { pub_key: owner(1), id: i, amount: 10 }).collect();
+            let elems: Vec<U2048> = utxos.iter().map(|utxo| subroutines::hash_to_prime(&params, &utxo.encode())).collect();
+            let witnesses = witnesses::create_all_mem_wit(&params, Stateless::get_state(), &elems);
+            let (state, _, _) = accumulator::batch_add(&params, Stateless::get_state(), &elems);
+            <State<Test>>::put(state);
+
+            // Two BLS owners spend together and a third one spends alone.
+            let tx_0 = Transaction {
+                inputs: vec![to_input(utxos[0], witnesses[0]), to_input(utxos[1], witnesses[1])],
+                outputs: vec![UTXO { amount: 20, ..outputs[0] }],
+                agg_witness: None,
+                bls_signature: None,
+            };
+            let tx_1 = bls_sign(bls_spend(utxos[2], outputs[1], witnesses[2]), &secrets[2..]);
+
+            // The key must hash to the owner and the transaction must carry a signature.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), bls_sign(tx_0.clone(), &secrets[1..])), Err("Invalid signature."));
+            let mut unsigned = bls_sign(tx_0.clone(), &secrets);
+            unsigned.bls_signature = None;
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), unsigned), Err("Invalid signature."));
+
+            let tx_0 = bls_sign(tx_0, &secrets);
+            let signed = vec![(bls::public_key(&secrets[0]), tx_0.bls_message(&utxos[0])),
+                              (bls::public_key(&secrets[1]), tx_0.bls_message(&utxos[1]))];
+            assert_eq!(bls::verify_aggregate(&signed, tx_0.bls_signature.as_ref().unwrap()), true);
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx_0), Ok(()));
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx_1), Ok(()));
+
+            // BLS and sr25519 transactions can share a block.
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), spend(utxos[3], outputs[2], witnesses[3])), Ok(()));
+            Stateless::on_finalize(System::block_number());
+            let new_elems: Vec<U2048> = [UTXO { amount: 20, ..outputs[0] }, outputs[1], outputs[2]].iter()
+                .map(|utxo| subroutines::hash_to_prime(&params, &utxo.encode()))
+                .collect();
+            let state = subroutines::mod_exp(U2048::from(2), new_elems[0] * new_elems[1] * new_elems[2], params.modulus);
+            assert_eq!(Stateless::get_state(), state);
+
+            // A forged signature is rejected when the transaction is added and leaves the block intact.
+            let utxo = UTXO { pub_key: bls_owner(&secrets[0]), id: 4, amount: 10 };
+            let elem = subroutines::hash_to_prime(&params, &utxo.encode());
+            let witness = Stateless::get_state();
+            assert_eq!(Stateless::mint(Origin::signed(1), utxo), Ok(()));
+            Stateless::on_finalize(System::block_number());
+            let state = Stateless::get_state();
+            assert_eq!(state, params.exp(&witness, elem));
+            let mut forged = bls_sign(bls_spend(utxo, outputs[3], witness), &secrets[..1]);
+            forged.bls_signature = Some(bls::sign(&secrets[1], &forged.bls_message(&utxo)));
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), forged), Err("Invalid signature."));
+            assert_eq!(Stateless::get_spent_coins().len(), 0);
+            Stateless::on_finalize(System::block_number());
+            assert_eq!(Stateless::get_state(), state);
+        });
+    }
+
+    #[test]
+    fn test_invalid_nonce() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
+            let utxo_0 = UTXO {
+                pub_key: owner(0),
+                id: 0,
+                amount: 10,
+            };
+            let utxo_1 = UTXO {
+                pub_key: owner(1),
+                id: 0,
+                amount: 10,
+            };
+
+            let (elem, nonce) = subroutines::hash_to_prime_with_nonce(&params, &utxo_0.encode());
+            let witness = Stateless::get_state();
+            let (state, _, _) = accumulator::batch_add(&params, witness, &vec![elem]);
+            <State<Test>>::put(state);
+
+            let mut tx = sign(Transaction {
+                inputs: vec![Input {
+                    utxo: utxo_0,
+                    witness: witness.encode(),
+                    nonce: nonce + 1,
+                    signature: Signature::default(),
+                }],
+                outputs: vec![utxo_1],
+                agg_witness: None,
+                bls_signature: None,
+            });
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx.clone()), Err(Error::NotPrime.as_str()));
+
+            tx.inputs[0].nonce = nonce;
+            assert_eq!(Stateless::addTransaction(Origin::signed(1), tx), Ok(()));
+        });
+    }
+
     #[test]
     fn test_mint() {
         with_externalities(&mut new_test_ext(), || {
-            Stateless::mint(Origin::signed(1), 3);
-            assert_eq!(Stateless::get_state(), U2048::from(8));
+            let params = TestParams::get();
+            let utxo = UTXO { pub_key: owner(0), id: 0, amount: 10 };
+            let elem = subroutines::hash_to_prime(&params, &utxo.encode());
+            assert_eq!(Stateless::mint(Origin::signed(1), utxo), Ok(()));
+            assert_eq!(Stateless::mint(Origin::signed(1), utxo), Err("Output already created in this block."));
+            assert_eq!(Stateless::get_state(), params.generator);
+
+            // Minted coins are added at the end of the block.
+            Stateless::on_finalize(System::block_number());
+            assert_eq!(Stateless::get_state(), subroutines::mod_exp(U2048::from(2), elem, params.modulus));
         });
     }
 
@@ -10,39 +10,39 @@ use runtime_io;
 /// the returned tuple is the product of the accumulated elements.
 /// NOTE: In the stateless blockchain model, after the validator commits the vector to the accumulator,
 /// users should immediately request membership witnesses for their committed bit using the returned "product" value.
-pub fn commit(accumulator: U2048, values: &[bool], indices: &[usize]) -> (U2048, U2048) {
+pub fn commit<G: Group>(group: &G, accumulator: G::Elem, values: &[bool], indices: &[usize]) -> (G::Elem, BigUint) {
     let elems: Vec<U2048> = values
         .into_iter()
         .enumerate()
         .filter(|(_, val)| **val)
-        .map(|(index, _)| subroutines::hash_to_prime(&indices[index].to_le_bytes()))
+        .map(|(index, _)| subroutines::hash_to_prime(group, &indices[index].to_le_bytes()))
         .collect();
-    let (state, product, _) = batch_add(accumulator, &elems);
+    let (state, product, _) = batch_add(group, accumulator, &elems);
     return (state, product);
 }
 
 /// Create an opening for a bit commitment. The current state of the accumulator should equal
 /// "old_state" raised to the "agg" power(product of aggregated elements) where the committed bit
 /// is contained in "agg".
-pub fn open(old_state: U2048, bit: bool, index: usize, agg: U2048) -> Witness {
-    let elem = subroutines::hash_to_prime(&index.to_le_bytes());
+pub fn open<G: Group>(group: &G, old_state: G::Elem, bit: bool, index: usize, agg: &BigUint) -> Result<Witness<G::Elem>, Error> {
+    let elem = subroutines::hash_to_prime(group, &index.to_le_bytes());
     if bit {
-        return Witness::MemWit(witnesses::mem_wit_create(old_state, agg, elem).unwrap());
+        return Ok(Witness::MemWit(witnesses::mem_wit_create(group, old_state, agg, &BigUint::from(elem))?));
     }
     else {
-        return Witness::NonMemWit(witnesses::non_mem_wit_create(old_state, agg, elem));
+        return Ok(Witness::NonMemWit(witnesses::non_mem_wit_create(group, old_state, agg, elem)?));
     }
 }
 
 /// Verify a membership/non-membership proof (produced by an opening) for a given bit commitment.
-pub fn verify(old_state: U2048, accumulator: U2048, bit: bool, index: usize, proof: Witness) -> bool {
-    let elem = subroutines::hash_to_prime(&index.to_le_bytes());
+pub fn verify<G: Group>(group: &G, old_state: G::Elem, accumulator: G::Elem, bit: bool, index: usize, proof: Witness<G::Elem>) -> bool {
+    let elem = subroutines::hash_to_prime(group, &index.to_le_bytes());
     if bit {
         match proof {
             Witness::MemWit(witness) => {
-                return witnesses::verify_mem_wit(accumulator, witness, elem);
+                return witnesses::verify_mem_wit(group, accumulator, witness, elem);
             },
-            Witness::NonMemWit(_) => {
+            Witness::NonMemWit(_) | Witness::AggNonMemWit(_) => {
                 return false;
             },
         }
@@ -50,7 +50,10 @@ pub fn verify(old_state: U2048, accumulator: U2048, bit: bool, index: usize, pro
     else {
         match proof {
             Witness::NonMemWit(witness) => {
-                return witnesses::verify_non_mem_wit(old_state,accumulator, witness, elem);
+                return witnesses::verify_non_mem_wit(group, old_state,accumulator, witness, elem);
+            },
+            Witness::AggNonMemWit(proof) => {
+                return witnesses::verify_agg_non_mem_wit(group, old_state, accumulator, proof, &BigUint::from(elem));
             },
             Witness::MemWit(_) => {
                 return false;
@@ -61,7 +64,7 @@ pub fn verify(old_state: U2048, accumulator: U2048, bit: bool, index: usize, pro
 
 /// Given a bit array and an array of corresponding indices, outputs the product of the "ones"
 /// elements and the product of the "zeros" elements.
-pub fn get_bit_elems(b: &[bool], i: &[usize]) -> (U2048, U2048) {
+pub fn get_bit_elems<G: Group>(group: &G, b: &[bool], i: &[usize]) -> (BigUint, BigUint) {
     let ones_indices: Vec<usize> = b
         .into_iter()
         .enumerate()
@@ -79,43 +82,45 @@ pub fn get_bit_elems(b: &[bool], i: &[usize]) -> (U2048, U2048) {
     let ones: Vec<U2048> = ones_indices
         .into_iter()
         .enumerate()
-        .map(|(_, index)| subroutines::hash_to_prime(&(i[index]).to_le_bytes()))
+        .map(|(_, index)| subroutines::hash_to_prime(group, &(i[index]).to_le_bytes()))
         .collect();
 
     let zeros: Vec<U2048> = zeros_indices
         .into_iter()
         .enumerate()
-        .map(|(_, index)| subroutines::hash_to_prime(&(i[index]).to_le_bytes()))
+        .map(|(_, index)| subroutines::hash_to_prime(group, &(i[index]).to_le_bytes()))
         .collect();
 
     let p_ones = subroutines::prime_product(&ones);
     let p_zeros = subroutines::prime_product(&zeros);
-    return (p_ones, p_zeros)
+    return (p_ones, p_zeros);
 }
 
 /// Batch opens a set of bit commitments. The accumulated values of the commitments must be contained in
 /// the inputted aggregated value(agg) and the current state of the accumulator must equal old_state^agg.
+/// The exclusion proof is an aggregated non-membership proof, so both proofs are constant size.
 /// This function has been slightly modified from the original specification. See page 20 of the paper for more info.
-pub fn batch_open(old_state: U2048, agg: U2048, b: &[bool], i: &[usize]) -> (Witness, Witness) {
-    let (p_ones, p_zeros) = get_bit_elems(b, i);
+pub fn batch_open<G: Group>(group: &G, old_state: G::Elem, agg: &BigUint, b: &[bool], i: &[usize]) -> Result<(Witness<G::Elem>, Witness<G::Elem>), Error> {
+    let (p_ones, p_zeros) = get_bit_elems(group, b, i);
 
-    let pi_inclusion = Witness::MemWit(witnesses::mem_wit_create(old_state, agg, p_ones).unwrap());
-    let pi_exclusion = Witness::NonMemWit(witnesses::non_mem_wit_create(old_state, agg, p_zeros));
+    let state = group.exp_big(&old_state, agg);
+    let pi_inclusion = Witness::MemWit(witnesses::mem_wit_create(group, old_state, agg, &p_ones)?);
+    let pi_exclusion = Witness::AggNonMemWit(witnesses::agg_non_mem_wit(group, old_state, state, agg, &p_zeros)?);
 
-    return (pi_inclusion, pi_exclusion);
+    return Ok((pi_inclusion, pi_exclusion));
 }
 
 /// Verifies a set of membership and non-membership witnesses for a set of bit commitments.
 /// This function has been slightly modified from the original specification. See page 20 of the paper for more info.
-pub fn batch_verify(old_state: U2048, accumulator: U2048, b: &[bool], i: &[usize], pi_i: Witness, pi_e: Witness) -> bool {
-    let (p_ones, p_zeros) = get_bit_elems(b, i);
+pub fn batch_verify<G: Group>(group: &G, old_state: G::Elem, accumulator: G::Elem, b: &[bool], i: &[usize], pi_i: Witness<G::Elem>, pi_e: Witness<G::Elem>) -> bool {
+    let (p_ones, p_zeros) = get_bit_elems(group, b, i);
 
     let ver_mem_result;
     match pi_i {
         Witness::MemWit(mem_wit) => {
-            ver_mem_result = witnesses::verify_mem_wit(accumulator, mem_wit, p_ones);
+            ver_mem_result = group.exp_big(&mem_wit, &p_ones) == accumulator;
         },
-        Witness::NonMemWit(_) => {
+        Witness::NonMemWit(_) | Witness::AggNonMemWit(_) => {
             return false;
         },
     }
@@ -126,7 +131,14 @@ pub fn batch_verify(old_state: U2048, accumulator: U2048, b: &[bool], i: &[usize
             return false;
         },
         Witness::NonMemWit(non_mem_wit) => {
-            ver_non_mem_result = witnesses::verify_non_mem_wit(old_state,accumulator,non_mem_wit, p_zeros); },
+            ver_non_mem_result = match p_zeros.to_u2048() {
+                Ok(p_zeros) => witnesses::verify_non_mem_wit(group, old_state, accumulator, non_mem_wit, p_zeros),
+                Err(_) => false,
+            };
+        },
+        Witness::AggNonMemWit(proof) => {
+            ver_non_mem_result = witnesses::verify_agg_non_mem_wit(group, old_state, accumulator, proof, &p_zeros);
+        },
     }
 
     return ver_mem_result && ver_non_mem_result;
@@ -140,17 +152,19 @@ pub fn batch_verify(old_state: U2048, accumulator: U2048, b: &[bool], i: &[usize
 ///        bits should be contained in this value.
 /// - b: New bit array.
 /// - i: Affected indices.
-pub fn update(accumulator: U2048, old_state: U2048, agg: U2048, b: &[bool], i: &[usize]) -> U2048 {
-    let (p_ones, p_zeros) = get_bit_elems(b, i);
+pub fn update<G: Group>(group: &G, accumulator: G::Elem, old_state: G::Elem, agg: &BigUint, b: &[bool], i: &[usize]) -> Result<G::Elem, Error> {
+    let (p_ones, p_zeros) = get_bit_elems(group, b, i);
 
     // Delete p_zeros elements
-    let mem_wit = witnesses::mem_wit_create(old_state, agg, p_zeros).unwrap();
-    let mut new_state = delete(accumulator, p_zeros, mem_wit).unwrap();
+    let mem_wit = witnesses::mem_wit_create(group, old_state, agg, &p_zeros)?;
+    if group.exp_big(&mem_wit, &p_zeros) != accumulator {
+        return Err(Error::InvalidWitness);
+    }
 
     // Add p_ones elements
-    new_state = add(new_state, p_ones);
+    let new_state = group.exp_big(&mem_wit, &p_ones);
 
-    return new_state;
+    return Ok(new_state);
 }
 
 #[cfg(test)]
@@ -159,59 +173,62 @@ mod tests {
 
     #[test]
     fn test_open_and_verify() {
+        let params = AccumulatorParams::default();
         // Commit vector
         let accumulator = U2048::from(2);
         let arr: [bool; 3] = [true, false, true];
-        let (state, product) = commit(accumulator, &arr, &[0, 1, 2]);
+        let (state, product) = commit(&params, accumulator, &arr, &[0, 1, 2]);
 
         // Check commit
-        let h_0 = subroutines::hash_to_prime(&(0 as usize).to_le_bytes());
-        let h_2 = subroutines::hash_to_prime(&(2 as usize).to_le_bytes());
-        assert_eq!(subroutines::mod_exp(accumulator, h_0*h_2, U2048::from_dec_str(MODULUS).unwrap()), state);
+        let h_0 = subroutines::hash_to_prime(&params, &(0 as usize).to_le_bytes());
+        let h_2 = subroutines::hash_to_prime(&params, &(2 as usize).to_le_bytes());
+        assert_eq!(subroutines::mod_exp(accumulator, h_0*h_2, params.modulus), state);
 
         // Open at two indices
-        let open_1 = open(U2048::from(2), false, 1, product);
-        let open_2 = open(U2048::from(2), true, 2, product);
+        let open_1 = open(&params, U2048::from(2), false, 1, &product).unwrap();
+        let open_2 = open(&params, U2048::from(2), true, 2, &product).unwrap();
 
         // Verify
-        assert_eq!(verify(accumulator, state, false, 1, open_1), true);
-        assert_eq!(verify(accumulator, state, true, 1, open_1), false);
-        assert_eq!(verify(accumulator, state, false, 1, open_2), false);
+        assert_eq!(verify(&params, accumulator, state, false, 1, open_1), true);
+        assert_eq!(verify(&params, accumulator, state, true, 1, open_1), false);
+        assert_eq!(verify(&params, accumulator, state, false, 1, open_2), false);
 
-        assert_eq!(verify(accumulator, state, true, 2, open_2), true);
-        assert_eq!(verify(accumulator, state, false, 2, open_2), false);
-        assert_eq!(verify(accumulator, state, true, 2, open_1), false);
+        assert_eq!(verify(&params, accumulator, state, true, 2, open_2), true);
+        assert_eq!(verify(&params, accumulator, state, false, 2, open_2), false);
+        assert_eq!(verify(&params, accumulator, state, true, 2, open_1), false);
     }
 
     #[test]
     fn test_get_bit_elems() {
+        let params = AccumulatorParams::default();
         let arr: [bool; 3] = [false, false, true];
         let indices = [0, 1, 5];
 
-        let h_0 = subroutines::hash_to_prime(&(0 as usize).to_le_bytes());
-        let h_1 = subroutines::hash_to_prime(&(1 as usize).to_le_bytes());
-        let h_5 = subroutines::hash_to_prime(&(5 as usize).to_le_bytes());
+        let h_0 = subroutines::hash_to_prime(&params, &(0 as usize).to_le_bytes());
+        let h_1 = subroutines::hash_to_prime(&params, &(1 as usize).to_le_bytes());
+        let h_5 = subroutines::hash_to_prime(&params, &(5 as usize).to_le_bytes());
 
-        let (p_ones, p_zeros) = get_bit_elems(&arr, &indices);
-        assert_eq!(p_ones, h_5);
-        assert_eq!(p_zeros, h_0 * h_1);
+        let (p_ones, p_zeros) = get_bit_elems(&params, &arr, &indices);
+        assert_eq!(p_ones, BigUint::from(h_5));
+        assert_eq!(p_zeros, BigUint::from(h_0) * h_1);
     }
 
     #[test]
     fn test_batch_open_and_verify() {
+        let params = AccumulatorParams::default();
         let accumulator = U2048::from(2);
         let arr: [bool; 6] = [true, false, true, false, false, true];
-        let (state, product) = commit(accumulator, &arr, &[0, 1, 2, 3, 4, 5]);
+        let (state, product) = commit(&params, accumulator, &arr, &[0, 1, 2, 3, 4, 5]);
 
-        let (i, e) = batch_open(accumulator, product, &[true, false, false, true], &[0, 3, 4, 5]);
+        let (i, e) = batch_open(&params, accumulator, &product, &[true, false, false, true], &[0, 3, 4, 5]).unwrap();
 
-        let h_0 = subroutines::hash_to_prime(&(0 as usize).to_le_bytes());
-        let h_3 = subroutines::hash_to_prime(&(3 as usize).to_le_bytes());
-        let h_4 = subroutines::hash_to_prime(&(4 as usize).to_le_bytes());
-        let h_5 = subroutines::hash_to_prime(&(5 as usize).to_le_bytes());
+        let h_0 = subroutines::hash_to_prime(&params, &(0 as usize).to_le_bytes());
+        let h_3 = subroutines::hash_to_prime(&params, &(3 as usize).to_le_bytes());
+        let h_4 = subroutines::hash_to_prime(&params, &(4 as usize).to_le_bytes());
+        let h_5 = subroutines::hash_to_prime(&params, &(5 as usize).to_le_bytes());
 
         // Manual check of openings
-        let ones_product = subroutines::prime_product(&vec![h_0, h_5]);
+        let ones_product = h_0 * h_5;
         let zeros_product = subroutines::prime_product(&vec![h_3, h_4]);
 
         let mut mem_result = false;
@@ -219,35 +236,57 @@ mod tests {
 
         match i {
             Witness::MemWit(mem_wit) => {
-                mem_result = witnesses::verify_mem_wit(state, mem_wit, ones_product);
+                mem_result = witnesses::verify_mem_wit(&params, state, mem_wit, ones_product);
             },
-            Witness::NonMemWit(_) => { },
+            Witness::NonMemWit(_) | Witness::AggNonMemWit(_) => { },
         }
 
         match e {
-            Witness::MemWit(_) => { },
-            Witness::NonMemWit(non_mem_wit) => {
-                non_mem_result = witnesses::verify_non_mem_wit(accumulator, state, non_mem_wit, zeros_product);
+            Witness::MemWit(_) | Witness::NonMemWit(_) => { },
+            Witness::AggNonMemWit(proof) => {
+                non_mem_result = witnesses::verify_agg_non_mem_wit(&params, accumulator, state, proof, &zeros_product);
             },
         }
         assert_eq!(mem_result && non_mem_result, true);
 
-        assert_eq!(batch_verify(accumulator, state, &[true, false, false, true], &[0, 3, 4, 5], i, e), true);
+        assert_eq!(batch_verify(&params, accumulator, state, &[true, false, false, true], &[0, 3, 4, 5], i, e), true);
+    }
+
+    #[test]
+    fn test_batch_open_many_zeros() {
+        // The product of more than 16 zero bits does not fit in a U2048.
+        let params = AccumulatorParams::new(U2048::from_dec_str("1522605027922533360535618378132637429718068114961380688657908494580122963258952897654000350692006139").unwrap(),
+                                            U2048::from(2), LAMBDA).unwrap();
+        let accumulator = params.generator;
+        let bits: Vec<bool> = (0..24).map(|index| index % 8 == 0).collect();
+        let indices: Vec<usize> = (0..24).collect();
+        let (state, product) = commit(&params, accumulator, &bits, &indices);
+        assert_eq!(get_bit_elems(&params, &bits, &indices).1.bits() > 2048, true);
+
+        let (i, e) = batch_open(&params, accumulator, &product, &bits, &indices).unwrap();
+        assert_eq!(batch_verify(&params, accumulator, state, &bits, &indices, i, e), true);
+        let mut flipped = bits.clone();
+        flipped[1] = true;
+        assert_eq!(batch_verify(&params, accumulator, state, &flipped, &indices, i, e), false);
     }
 
     #[test]
     fn test_update() {
+        let params = AccumulatorParams::default();
         let accumulator = U2048::from(2);
         let arr: [bool; 6] = [true, false, true, false, false, true];
-        let (state, product) = commit(accumulator, &arr, &[0, 1, 2, 3, 4, 5]);
+        let (state, product) = commit(&params, accumulator, &arr, &[0, 1, 2, 3, 4, 5]);
 
-        let h_0 = subroutines::hash_to_prime(&(0 as usize).to_le_bytes());
-        let h_3 = subroutines::hash_to_prime(&(3 as usize).to_le_bytes());
-        let h_4 = subroutines::hash_to_prime(&(4 as usize).to_le_bytes());
+        let h_0 = subroutines::hash_to_prime(&params, &(0 as usize).to_le_bytes());
+        let h_3 = subroutines::hash_to_prime(&params, &(3 as usize).to_le_bytes());
+        let h_4 = subroutines::hash_to_prime(&params, &(4 as usize).to_le_bytes());
 
         // Missing: checking that inputs are valid
-        let new_state = update(state, accumulator, product, &[false, true, true, false], &[2, 3, 4, 5]);
-        assert_eq!(new_state, subroutines::mod_exp(accumulator, h_0 * h_3 * h_4, U2048::from_dec_str(MODULUS).unwrap()));
+        let new_state = update(&params, state, accumulator, &product, &[false, true, true, false], &[2, 3, 4, 5]).unwrap();
+        assert_eq!(new_state, subroutines::mod_exp(accumulator, h_0 * h_3 * h_4, params.modulus));
+
+        // The deleted bits must be contained in the aggregated value.
+        assert_eq!(update(&params, state, accumulator, &BigUint::from(U2048::from(3)), &[false, true, true, false], &[2, 3, 4, 5]), Err(Error::NotDivisible));
     }
 
 }
\ No newline at end of file
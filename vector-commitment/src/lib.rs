@@ -28,12 +28,13 @@ pub struct Transaction {
 pub trait Trait: system::Trait {
     type Event: From<Event> + Into<<Self as system::Trait>::Event>;
     type KeySpace: Get<u8>;
+    type Params: Get<AccumulatorParams>;
 }
 
 // This module's storage items.
 decl_storage! {
     trait Store for Module<T: Trait> as StatelessAccounts {
-        State get(get_state): U2048 = U2048::from(2);  // Use 2 as an arbitrary generator with "unknown" order.
+        State: Option<U2048>;  // Unset until the first update. See "get_state".
         WitnessData get(get_witness_data): Vec<(U2048, U2048)>;
         NewKeyValuePairs: Vec<(u8, u8)>;
     }
@@ -41,9 +42,9 @@ decl_storage! {
 
 decl_event!(
     pub enum Event {
-        TokensMinted(U2048, U2048),
-        Deletion(U2048, U2048, U2048),
-        Addition(U2048, U2048, U2048),
+        TokensMinted(U2048, BigUint),
+        Deletion(U2048, BigUint, U2048),
+        Addition(U2048, BigUint, U2048),
     }
 );
 
@@ -57,7 +58,7 @@ decl_module! {
         /// NOTE: The key must not exist initially.
         pub fn mint(origin, key: u8, amount: u8) -> Result {
             ensure_signed(origin)?;
-            let (state, product) = vc::commit(State::get(), &[key as usize], &[amount]);
+            let (state, product) = vc::commit(&T::Params::get(), Self::get_state(), &[key as usize], &[amount]);
             State::put(state);
             Self::deposit_event(Event::TokensMinted(state, product));
             Ok(())
@@ -69,11 +70,12 @@ decl_module! {
         /// prevent having to pass in the product of all of the elements in the accumulator.
         pub fn add_transaction(origin, transaction: Transaction, old_state: U2048) -> Result {
             ensure_signed(origin)?;
+            let params = T::Params::get();
             // Get the opening of the sender
             let (pi_i_sender, pi_e_sender) = transaction.sender_opening;
 
             // Verify that it is valid
-            ensure!(vc::verify_at_key(old_state, State::get(), transaction.sender_key as usize,
+            ensure!(vc::verify_at_key(&params, old_state, Self::get_state(), transaction.sender_key as usize,
             transaction.sender_balance, pi_i_sender, pi_e_sender), "Opening is invalid.");
 
             // Ensure that the sender isn't spending more than balance
@@ -81,7 +83,7 @@ decl_module! {
 
             // Verify receiver opening
             let (pi_i_receiver, pi_e_receiver) = transaction.receiver_opening;
-            ensure!(vc::verify_at_key(old_state, State::get(), transaction.receiver_key as usize,
+            ensure!(vc::verify_at_key(&params, old_state, Self::get_state(), transaction.receiver_key as usize,
                     transaction.receiver_balance, pi_i_receiver, pi_e_receiver), "Opening is invalid.");
 
             // Add membership proofs to temporary vector to be processed later
@@ -102,27 +104,8 @@ decl_module! {
         }
 
         fn on_finalize() {
-            // Remove previous key-value commitment.
-            let (state, product, proof) = accumulator::batch_delete(State::get(), &WitnessData::get());
-            Self::deposit_event(Event::Deletion(state, product, proof));
-
-            // Get the integer representations of the new key-value pairs.
-            let elems: Vec<U2048> = NewKeyValuePairs::get()
-                .into_iter()
-                .enumerate()
-                .map(|(_, (key, value))| -> U2048 {
-                    let (binary_vec, indices) = vc::convert_key_value(&[key as usize], &[value]);
-                    let (p_ones, _) = binary::get_bit_elems(&binary_vec, &indices);
-                    return p_ones;
-                })
-                .collect();
-
-            // Add updated key-value pairs.
-            let (state, product, proof) = accumulator::batch_add(state, &elems);
-            Self::deposit_event(Event::Addition(state, product, proof));
-
-            // Update accumulator
-            State::put(state);
+            // If the batch is invalid, the accumulator is left unchanged.
+            let _ = Self::update_state();
 
             // Clear storage items
             WitnessData::kill();
@@ -131,6 +114,43 @@ decl_module! {
     }
 }
 
+impl<T: Trait> Module<T> {
+    /// Returns the current state of the accumulator. Before any key-value pairs are committed, this
+    /// is the generator from the accumulator parameters.
+    pub fn get_state() -> U2048 {
+        State::get().unwrap_or(T::Params::get().generator)
+    }
+
+    /// Removes the previous key-value commitments and adds the updated ones. Nothing is written or
+    /// emitted unless both batches succeed.
+    fn update_state() -> core::result::Result<(), Error> {
+        let params = T::Params::get();
+        let witness_data = WitnessData::get();
+        if witness_data.is_empty() {
+            return Ok(());
+        }
+
+        // Remove previous key-value commitment.
+        let (del_state, del_product, del_proof) = accumulator::batch_delete(&params, Self::get_state(), &witness_data)?;
+
+        // Get the integer representations of the new key-value pairs.
+        let elems = NewKeyValuePairs::get()
+            .into_iter()
+            .map(|(key, value)| vc::get_key_value_elem(&params, key as usize, value))
+            .collect::<core::result::Result<Vec<U2048>, Error>>()?;
+
+        // Add updated key-value pairs.
+        let (state, product, proof) = accumulator::batch_add(&params, del_state, &elems);
+
+        Self::deposit_event(Event::Deletion(del_state, del_product, del_proof));
+        Self::deposit_event(Event::Addition(state, product, proof));
+
+        // Update accumulator
+        State::put(state);
+        return Ok(());
+    }
+}
+
 /// Tests for this module
 #[cfg(test)]
 mod tests {
@@ -160,6 +180,13 @@ mod tests {
         pub const KeySpace: u8 = 255;
     }
 
+    pub struct TestParams;
+    impl Get<AccumulatorParams> for TestParams {
+        fn get() -> AccumulatorParams {
+            AccumulatorParams::default()
+        }
+    }
+
     impl system::Trait for Test {
         type Origin = Origin;
         type Call = ();
@@ -182,6 +209,7 @@ mod tests {
     impl Trait for Test {
         type Event = ();
         type KeySpace = KeySpace;
+        type Params = TestParams;
     }
 
     type StatelessAccounts = Module<Test>;
@@ -196,19 +224,21 @@ mod tests {
     #[test]
     fn test_mint() {
         with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
             let key: u8 = 1;
             let value: u8 = 10;
             StatelessAccounts::mint(Origin::signed(1), key, value);
 
             let (binary_vec, indices) = vc::convert_key_value(&[key as usize], &[value]);
-            let (p_ones, _) = binary::get_bit_elems(&binary_vec, &indices);
-            assert_eq!(StatelessAccounts::get_state(), subroutines::mod_exp(U2048::from(2), p_ones, U2048::from_dec_str(MODULUS).unwrap()));
+            let (p_ones, _) = binary::get_bit_elems(&params, &binary_vec, &indices);
+            assert_eq!(StatelessAccounts::get_state(), params.exp_big(&U2048::from(2), &p_ones));
         });
     }
 
     #[test]
     fn test_transaction() {
         with_externalities(&mut new_test_ext(), || {
+            let params = TestParams::get();
             let generator = StatelessAccounts::get_state();
 
             // Define keys for alice and bob
@@ -224,16 +254,16 @@ mod tests {
             StatelessAccounts::mint(Origin::signed(1), bob_key, bob_balance);
 
             // Derive integer representations for manual testing
-            let alice_elem = vc::get_key_value_elem(alice_key as usize, alice_balance);  // This value would be received from the emitted event.
-            let bob_elem = vc::get_key_value_elem(bob_key as usize, bob_balance);   // This value would be received from the emitted event.
-            let product = alice_elem * bob_elem;
+            let alice_elem = vc::get_key_value_elem(&params, alice_key as usize, alice_balance).unwrap();  // This value would be received from the emitted event.
+            let bob_elem = vc::get_key_value_elem(&params, bob_key as usize, bob_balance).unwrap();   // This value would be received from the emitted event.
+            let product = BigUint::from(alice_elem) * bob_elem;
 
             // Get state after minting
             let state_after_mint = StatelessAccounts::get_state();
 
             // Get openings for each user
-            let (alice_pi_i, alice_pi_e) = vc::open_at_key(generator, product, alice_key as usize, alice_balance);
-            let (bob_pi_i, bob_pi_e) = vc::open_at_key(generator, product, bob_key as usize, bob_balance);
+            let (alice_pi_i, alice_pi_e) = vc::open_at_key(&params, generator, &product, alice_key as usize, alice_balance).unwrap();
+            let (bob_pi_i, bob_pi_e) = vc::open_at_key(&params, generator, &product, bob_key as usize, bob_balance).unwrap();
 
             // Construct transaction
             let transaction = Transaction {
@@ -252,7 +282,7 @@ mod tests {
             StatelessAccounts::add_transaction(Origin::signed(1), transaction, generator);
 
             // Manually get the state after deletion for manual testing
-            let (state_after_del, _, _) = batch_delete(state_after_mint, &StatelessAccounts::get_witness_data());
+            let (state_after_del, _, _) = batch_delete(&params, state_after_mint, &StatelessAccounts::get_witness_data()).unwrap();
 
             // Finalize block
             StatelessAccounts::on_finalize(System::block_number());
@@ -261,16 +291,17 @@ mod tests {
             let new_state = StatelessAccounts::get_state();
 
             // Derive integer representations for alice and bob's new key-value stores
-            let new_alice_elem = vc::get_key_value_elem(alice_key as usize, alice_balance-3);  // This value would be received from the emitted event.
-            let new_bob_elem = vc::get_key_value_elem(bob_key as usize, bob_balance+3);  // This value would be received from the emitted event.
+            let new_alice_elem = vc::get_key_value_elem(&params, alice_key as usize, alice_balance-3).unwrap();  // This value would be received from the emitted event.
+            let new_bob_elem = vc::get_key_value_elem(&params, bob_key as usize, bob_balance+3).unwrap();  // This value would be received from the emitted event.
 
             // Create openings with the new balances
-            let (alice_pi_i_new, alice_pi_e_new) = vc::open_at_key(state_after_del, new_alice_elem*new_bob_elem, alice_key as usize, alice_balance-3);
-            let (bob_pi_i_new, bob_pi_e_new) = vc::open_at_key(state_after_del, new_alice_elem*new_bob_elem, bob_key as usize, bob_balance+3);
+            let new_product = BigUint::from(new_alice_elem) * new_bob_elem;
+            let (alice_pi_i_new, alice_pi_e_new) = vc::open_at_key(&params, state_after_del, &new_product, alice_key as usize, alice_balance-3).unwrap();
+            let (bob_pi_i_new, bob_pi_e_new) = vc::open_at_key(&params, state_after_del, &new_product, bob_key as usize, bob_balance+3).unwrap();
 
             // Verify that the openings are valid
-            assert_eq!(vc::verify_at_key(state_after_del, new_state, alice_key as usize, alice_balance-3, alice_pi_i_new, alice_pi_e_new), true);
-            assert_eq!(vc::verify_at_key(state_after_del, new_state, bob_key as usize, bob_balance+3, bob_pi_i_new, bob_pi_e_new), true);
+            assert_eq!(vc::verify_at_key(&params, state_after_del, new_state, alice_key as usize, alice_balance-3, alice_pi_i_new, alice_pi_e_new), true);
+            assert_eq!(vc::verify_at_key(&params, state_after_del, new_state, bob_key as usize, bob_balance+3, bob_pi_i_new, bob_pi_e_new), true);
         });
     }
 }
@@ -9,28 +9,28 @@ use crate::binary;
 type ValueType = u8;
 
 /// Commit to a set of keys and corresponding values.
-pub fn commit(accumulator: U2048, keys: &[usize], values: &[ValueType]) -> (U2048, U2048) {
+pub fn commit<G: Group>(group: &G, accumulator: G::Elem, keys: &[usize], values: &[ValueType]) -> (G::Elem, BigUint) {
     let (binary_vec, indices) = convert_key_value(keys, values);
-    return binary::commit(accumulator, &binary_vec, &indices);
+    return binary::commit(group, accumulator, &binary_vec, &indices);
 }
 
 /// Open a commitment for a value at a specific key. This function would be immediately called by a
 /// user following a relevant state commitment.
-pub fn open_at_key(old_state: U2048, product: U2048, key: usize, value: ValueType) -> (Witness, Witness) {
+pub fn open_at_key<G: Group>(group: &G, old_state: G::Elem, product: &BigUint, key: usize, value: ValueType) -> Result<(Witness<G::Elem>, Witness<G::Elem>), Error> {
     let (binary_vec, indices) = convert_key_value(&[key], &[value]);
-    return binary::batch_open(old_state, product, &binary_vec, &indices);
+    return binary::batch_open(group, old_state, product, &binary_vec, &indices);
 }
 
 /// Verify a commitment for a value at a specific key.
-pub fn verify_at_key(old_state: U2048, accumulator: U2048, key: usize, value: ValueType, pi_i: Witness, pi_e: Witness) -> bool {
+pub fn verify_at_key<G: Group>(group: &G, old_state: G::Elem, accumulator: G::Elem, key: usize, value: ValueType, pi_i: Witness<G::Elem>, pi_e: Witness<G::Elem>) -> bool {
     let (binary_vec, indices) = convert_key_value(&[key], &[value]);
-    return binary::batch_verify(old_state, accumulator, &binary_vec, &indices, pi_i, pi_e);
+    return binary::batch_verify(group, old_state, accumulator, &binary_vec, &indices, pi_i, pi_e);
 }
 
-/// Update the values for a set of keys. Assumes key-value pairs are valid.
-pub fn update(accumulator: U2048, old_state: U2048, agg: U2048, keys: &[usize], values: &[ValueType]) -> U2048 {
+/// Update the values for a set of keys. Fails if the old key-value pairs are not contained in "agg".
+pub fn update<G: Group>(group: &G, accumulator: G::Elem, old_state: G::Elem, agg: &BigUint, keys: &[usize], values: &[ValueType]) -> Result<G::Elem, Error> {
     let (binary_vec, indices) = convert_key_value(keys, values);
-    return binary::update(accumulator, old_state, agg, &binary_vec, &indices);
+    return binary::update(group, accumulator, old_state, agg, &binary_vec, &indices);
 }
 
 /// Converts key-value pairs into a binary representation of the values along with corresponding
@@ -56,11 +56,11 @@ pub fn to_binary(elem: ValueType) -> Vec<bool> {
 }
 
 /// Quick helper function that gets the product of the accumulated elements for a given
-/// key-value pair.
-pub fn get_key_value_elem(key: usize, value: ValueType) -> U2048 {
+/// key-value pair. Fails if the product does not fit in a U2048.
+pub fn get_key_value_elem<G: Group>(group: &G, key: usize, value: ValueType) -> Result<U2048, Error> {
     let (binary_vec, indices) = convert_key_value(&[key], &[value]);
-    let (elem, _) = binary::get_bit_elems(&binary_vec, &indices);
-    return elem;
+    let (elem, _) = binary::get_bit_elems(group, &binary_vec, &indices);
+    return elem.to_u2048();
 }
 
 #[cfg(test)]
@@ -76,19 +76,20 @@ mod tests {
 
     #[test]
     fn test_commit() {
+        let params = AccumulatorParams::default();
         let accumulator: U2048 = U2048::from(2);
         let keys = [0, 1];
         let values = vec![4, 7];
 
-        let (new_accumulator, _) = commit(accumulator, &keys, &values);
+        let (new_accumulator, _) = commit(&params, accumulator, &keys, &values);
 
         // Manual check
-        let check_product = subroutines::hash_to_prime(&(5 as usize).to_le_bytes())
-            * subroutines::hash_to_prime(&(13 as usize).to_le_bytes())
-            * subroutines::hash_to_prime(&(14 as usize).to_le_bytes())
-            * subroutines::hash_to_prime(&(15 as usize).to_le_bytes());
+        let check_product = subroutines::hash_to_prime(&params, &(5 as usize).to_le_bytes())
+            * subroutines::hash_to_prime(&params, &(13 as usize).to_le_bytes())
+            * subroutines::hash_to_prime(&params, &(14 as usize).to_le_bytes())
+            * subroutines::hash_to_prime(&params, &(15 as usize).to_le_bytes());
 
-        assert_eq!(new_accumulator, subroutines::mod_exp(U2048::from(2), U2048::from(check_product), U2048::from_dec_str(MODULUS).unwrap()));
+        assert_eq!(new_accumulator, subroutines::mod_exp(U2048::from(2), U2048::from(check_product), params.modulus));
     }
 
     #[test]
@@ -103,28 +104,30 @@ mod tests {
 
     #[test]
     fn test_vc_open_and_verify() {
+        let params = AccumulatorParams::default();
         let accumulator: U2048 = U2048::from(2);
         let keys = vec![0, 1];
         let values = vec![4, 7];
-        let (new_accumulator, product) = commit(accumulator, &keys, &values);
+        let (new_accumulator, product) = commit(&params, accumulator, &keys, &values);
 
-        let (pi_i, pi_e) = open_at_key(accumulator, product, 1, 7);
+        let (pi_i, pi_e) = open_at_key(&params, accumulator, &product, 1, 7).unwrap();
 
-        assert_eq!(verify_at_key(accumulator, new_accumulator, 1, 7, pi_i, pi_e), true);
-        assert_eq!(verify_at_key(accumulator, new_accumulator, 0, 7, pi_i, pi_e), false);
-        assert_eq!(verify_at_key(accumulator, new_accumulator, 1, 4, pi_i, pi_e), false);
+        assert_eq!(verify_at_key(&params, accumulator, new_accumulator, 1, 7, pi_i, pi_e), true);
+        assert_eq!(verify_at_key(&params, accumulator, new_accumulator, 0, 7, pi_i, pi_e), false);
+        assert_eq!(verify_at_key(&params, accumulator, new_accumulator, 1, 4, pi_i, pi_e), false);
     }
 
     #[test]
     fn test_get_key_value_elem() {
+        let params = AccumulatorParams::default();
         let (key, value): (usize, u8) = (0, 5);
-        let elem = get_key_value_elem(key, value);
+        let elem = get_key_value_elem(&params, key, value).unwrap();
 
         let bv = to_binary(value);
         let indices: Vec<usize> = (0..8).collect();
-        let (state, _) = binary::commit(U2048::from(2), &bv, &indices);
+        let (state, _) = binary::commit(&params, U2048::from(2), &bv, &indices);
 
-        assert_eq!(state, subroutines::mod_exp(U2048::from(2), elem, U2048::from_dec_str(MODULUS).unwrap()))
+        assert_eq!(state, subroutines::mod_exp(U2048::from(2), elem, params.modulus))
     }
 
 }
\ No newline at end of file